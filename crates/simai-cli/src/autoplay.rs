@@ -0,0 +1,58 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, bail};
+use simai::{
+	play::{Autoplay, Replay},
+	timeline::Timeline,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Seconds a tap is held down for
+	#[arg(short, long, default_value_t = Autoplay::default().tap)]
+	tap: f64,
+	/// Write the inputs as JSON
+	#[arg(long)]
+	json: bool,
+	/// Write a replay of the chart, binary unless --json is given
+	#[arg(long)]
+	replay: bool,
+	/// Where to write the inputs, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let mut simai = crate::read_simai(&args.file)?;
+	let chart = simai
+		.chart
+		.get_mut(args.level)
+		.and_then(Option::take)
+		.with_context(|| format!("no inote_{} in {}", args.level, args.file.display()))?;
+	let inputs = Autoplay { tap: args.tap }.inputs(&Timeline::from_chart(&chart));
+
+	if args.replay {
+		let replay = Replay::new(&chart, u8::try_from(args.level)?, simai.first.unwrap_or(0.), inputs);
+		if args.json {
+			return crate::write_output(
+				args.output.as_deref(),
+				&(serde_json::to_string_pretty(&replay)? + "\n"),
+			);
+		}
+		let Some(output) = &args.output else { bail!("binary replays need --output") };
+		let mut bytes = vec![];
+		replay.write(&mut bytes)?;
+		return fs::write(output, bytes).with_context(|| format!("writing {}", output.display()));
+	}
+	let text = if args.json {
+		serde_json::to_string_pretty(&inputs)? + "\n"
+	} else {
+		inputs.iter().map(|i| format!("{}\n", i)).collect()
+	};
+	crate::write_output(args.output.as_deref(), &text)
+}
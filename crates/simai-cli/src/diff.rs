@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
+use simai::{
+	diff::{Equivalence, diff},
+	timeline::Timeline,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The earlier maidata file
+	old: PathBuf,
+	/// The revised maidata file
+	new: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Seconds two notes may be apart and still count as the same note
+	#[arg(short, long, default_value_t = 0.001)]
+	tolerance: f64,
+	/// Only check whether the charts play the same, failing with the first difference if not
+	#[arg(short, long)]
+	check: bool,
+	/// Print the changes as JSON
+	#[arg(long)]
+	json: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let (old, new) =
+		(crate::read_chart(&args.old, args.level)?, crate::read_chart(&args.new, args.level)?);
+	if args.check {
+		let equivalence = Equivalence::check(&old, &new, args.tolerance);
+		if args.json {
+			println!("{}", serde_json::to_string_pretty(&equivalence)?);
+		}
+		if !equivalence.is_equivalent() {
+			bail!("{}", equivalence);
+		}
+		if !args.json {
+			println!("{}", equivalence);
+		}
+		return Ok(());
+	}
+	let (old, new) = (Timeline::from_chart(&old), Timeline::from_chart(&new));
+	let diff = diff(&old, &new, args.tolerance);
+
+	if args.json {
+		println!("{}", serde_json::to_string_pretty(&diff)?);
+	} else {
+		print!("{}", diff);
+	}
+	Ok(())
+}
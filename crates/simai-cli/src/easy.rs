@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use simai::transform::Simplify;
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Divisor of the grid notes are moved onto, one of 1, 2, 3, 4, 6, 8, 12, 16, 24, ...
+	#[arg(short, long, default_value_t = 8)]
+	grid: u32,
+	/// Seconds a note must come after the previous one to be kept, which caps the notes per
+	/// second at one over it
+	#[arg(short, long, default_value_t = 0.25)]
+	min_gap: f64,
+	/// Where to write the simplified chart, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let simplify = Simplify { grid: args.grid, min_gap: args.min_gap };
+	simplify.check()?;
+	let chart = simplify.chart(&crate::read_chart(&args.file, args.level)?)?;
+	crate::write_output(args.output.as_deref(), &format!("{}\n", chart))
+}
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use simai::convert::{Ma2, Majdata};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+	Majdata,
+	Ma2,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file to export from
+	input: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	#[arg(short, long, value_enum)]
+	format: Format,
+	/// Where to write the result, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let chart = crate::read_chart(&args.input, args.level)?;
+
+	let text = match args.format {
+		Format::Majdata => serde_json::to_string(&Majdata::from_chart(&chart))?,
+		Format::Ma2 => Ma2::from_chart(&chart).to_string(),
+	};
+	crate::write_output(args.output.as_deref(), &text)
+}
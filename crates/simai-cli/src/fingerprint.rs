@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use simai::library::{fingerprint, normalized, notes_hash};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Only this chart, as in `inote_N`; all of them if omitted
+	#[arg(short, long)]
+	level: Option<usize>,
+	/// Print the normalized chart the hashes are taken of instead
+	#[arg(short, long)]
+	normalized: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let simai = crate::read_simai(&args.file)?;
+	for (level, chart) in simai.chart.iter().enumerate() {
+		let Some(chart) = chart else { continue };
+		if args.level.is_some_and(|l| l != level) {
+			continue;
+		}
+		if args.normalized {
+			print!("{}", normalized(chart));
+			continue;
+		}
+		let full = fingerprint(&simai, level).unwrap_or_default();
+		println!("inote_{}: {} notes {}", level, full, notes_hash(chart));
+	}
+	Ok(())
+}
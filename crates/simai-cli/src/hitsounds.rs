@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use simai::{
+	audio::{Hitsounds, mix},
+	timeline::Timeline,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Where to write the WAV file
+	#[arg(short, long)]
+	output: PathBuf,
+	/// Click on every beat
+	#[arg(short, long)]
+	metronome: bool,
+	/// A WAV track to mix the hitsounds into, lined up by `&first`
+	#[arg(long)]
+	music: Option<PathBuf>,
+	/// Loudness of the hitsounds, 1 for about full scale
+	#[arg(long, default_value_t = Hitsounds::default().volume)]
+	volume: f32,
+	/// Sample rate of the output when there is no music, which sets it otherwise
+	#[arg(long, default_value_t = Hitsounds::default().sample_rate)]
+	sample_rate: u32,
+}
+
+/// Reads a WAV file as interleaved samples between -1 and 1.
+fn read_wav(path: &Path) -> anyhow::Result<(WavSpec, Vec<f32>)> {
+	let mut reader = WavReader::open(path).with_context(|| format!("reading {}", path.display()))?;
+	let spec = reader.spec();
+	let samples = match spec.sample_format {
+		SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+		SampleFormat::Int => {
+			let scale = (1u32 << (spec.bits_per_sample - 1)) as f32;
+			reader.samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect::<Result<_, _>>()?
+		}
+	};
+	Ok((spec, samples))
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let mut simai = crate::read_simai(&args.file)?;
+	let chart = simai
+		.chart
+		.get_mut(args.level)
+		.and_then(Option::take)
+		.with_context(|| format!("no inote_{} in {}", args.level, args.file.display()))?;
+	let timeline = Timeline::from_chart(&chart);
+
+	let (sample_rate, channels, mut music) = match &args.music {
+		Some(path) => {
+			let (spec, samples) = read_wav(path)?;
+			(spec.sample_rate, spec.channels, samples)
+		}
+		None => (args.sample_rate, 1, vec![]),
+	};
+	let hitsounds = Hitsounds { sample_rate, metronome: args.metronome, volume: args.volume };
+	let sounds = hitsounds.render(&timeline, simai.first.unwrap_or(0.));
+	mix(&mut music, channels as usize, &sounds);
+
+	let spec =
+		WavSpec { channels, sample_rate, bits_per_sample: 16, sample_format: SampleFormat::Int };
+	let mut writer = WavWriter::create(&args.output, spec)
+		.with_context(|| format!("writing {}", args.output.display()))?;
+	for sample in music {
+		writer.write_sample((sample.clamp(-1., 1.) * i16::MAX as f32) as i16)?;
+	}
+	writer.finalize()?;
+	Ok(())
+}
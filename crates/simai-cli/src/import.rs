@@ -0,0 +1,67 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, bail, ensure};
+use simai::{
+	convert::{Ma2, Osu},
+	def::Key,
+	parse::Simai,
+	timeline::Quantizer,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The .ma2 or .osu file to import
+	input: PathBuf,
+	/// Difficulty index to write the chart to, as in `inote_N`
+	#[arg(short, long, default_value_t = 5)]
+	level: usize,
+	/// Keys for the columns of an osu!mania map from left to right, e.g. 7812
+	#[arg(short, long)]
+	keys: Option<String>,
+	/// Where to write the maidata, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	ensure!((1..8).contains(&args.level), "level must be between 1 and 7");
+	let text =
+		fs::read_to_string(&args.input).with_context(|| format!("reading {}", args.input.display()))?;
+
+	let mut simai = Simai::default();
+	let chart = match args.input.extension().and_then(|e| e.to_str()) {
+		Some("ma2") => text.parse::<Ma2>()?.to_chart()?,
+		Some("osu") => {
+			let osu: Osu = text.parse()?;
+			let mapping: Vec<Key> = match &args.keys {
+				Some(keys) => keys.chars().map(parse_key).collect::<anyhow::Result<_>>()?,
+				None => Osu::default_mapping(osu.columns).with_context(|| {
+					format!("{} columns do not fit on 8 keys, map them with --keys", osu.columns)
+				})?,
+			};
+			ensure!(
+				mapping.len() == osu.columns,
+				"--keys has {} keys for {} columns",
+				mapping.len(),
+				osu.columns
+			);
+			simai.title = osu.title.clone();
+			simai.artist = osu.artist.clone();
+			simai.first = Some(osu.first());
+			let quantized = osu.quantize(&mapping, &Quantizer::default())?;
+			if quantized.max_error() > 0.0005 {
+				eprintln!("notes moved by up to {:.1} ms", quantized.max_error() * 1000.);
+			}
+			simai::write::chart(&quantized.items).parse()?
+		}
+		_ => bail!("{}: expected a .ma2 or .osu file", args.input.display()),
+	};
+	simai.chart[args.level] = Some(chart);
+
+	crate::write_output(args.output.as_deref(), &simai.to_string())
+}
+
+fn parse_key(c: char) -> anyhow::Result<Key> {
+	ensure!(('1'..='8').contains(&c), "invalid key: {}", c);
+	Ok(Key::from(c))
+}
@@ -0,0 +1,66 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context;
+use simai::{
+	play::{Input, Judge, Judgment, REPLAY_MAGIC, Replay, Scorecard},
+	timeline::Timeline,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// A replay, binary or JSON, or a JSON list of inputs as written by `simai autoplay`
+	inputs: PathBuf,
+	/// List the judgment of every note
+	#[arg(short, long)]
+	notes: bool,
+	/// Print the judgments and the scorecard as JSON
+	#[arg(long)]
+	json: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let chart = crate::read_chart(&args.file, args.level)?;
+	let timeline = Timeline::from_chart(&chart);
+	let bytes =
+		fs::read(&args.inputs).with_context(|| format!("reading {}", args.inputs.display()))?;
+	let replay = if bytes.starts_with(REPLAY_MAGIC) {
+		Some(Replay::read(&mut bytes.as_slice())?)
+	} else {
+		serde_json::from_slice::<Replay>(&bytes).ok()
+	};
+	let inputs = match replay {
+		Some(replay) => {
+			replay.check(&chart, u8::try_from(args.level)?)?;
+			replay.judged_inputs()
+		}
+		None => serde_json::from_slice::<Vec<Input>>(&bytes)
+			.with_context(|| format!("reading {}", args.inputs.display()))?,
+	};
+	let judged = Judge::default().judge(&timeline, &inputs);
+	let card = Scorecard::new(&judged);
+
+	if args.json {
+		let json = serde_json::json!({ "notes": judged, "scorecard": card });
+		println!("{}", serde_json::to_string_pretty(&json)?);
+		return Ok(());
+	}
+	if args.notes {
+		for j in &judged {
+			let offset = j.offset.map_or(String::new(), |o| format!(" ({:+.1}ms)", o * 1000.));
+			let kind = if j.is_break { format!("break {}", j.kind) } else { j.kind.to_string() };
+			println!("{:.3}s {}: {}{}", j.time, kind, j.judgment, offset);
+		}
+	}
+	for judgment in Judgment::ALL {
+		println!("{}: {}", judgment, card.counts.get(judgment));
+	}
+	println!("max combo: {}", card.max_combo);
+	println!("dx score: {} / {}", card.dx_score, card.max_dx_score);
+	println!("achievement: {:.4}%", card.achievement);
+	Ok(())
+}
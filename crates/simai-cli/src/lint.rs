@@ -0,0 +1,69 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, bail};
+use simai::{
+	lint::{Config, Severity, lint},
+	parse::{Simai, line_col},
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file to check
+	input: PathBuf,
+	/// Only check `inote_N`, all charts if omitted
+	#[arg(short, long)]
+	level: Option<usize>,
+	/// TOML file turning rules off, changing their severity or setting the playability limits
+	#[arg(short, long)]
+	config: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let config: Config = match &args.config {
+		Some(path) => {
+			let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+			toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?
+		}
+		None => Config::default(),
+	};
+	if let Some(name) = config.unknown_rules().first() {
+		bail!("unknown lint rule: {}", name);
+	}
+
+	// unlike the other commands, charts with parse errors are checked, with the errors reported
+	let text =
+		fs::read_to_string(&args.input).with_context(|| format!("reading {}", args.input.display()))?;
+	let simai: Simai = text.parse()?;
+
+	let mut errors = 0;
+	for (i, chart) in simai.chart.iter().enumerate() {
+		let Some(chart) = chart else { continue };
+		if args.level.is_some_and(|l| l != i) {
+			continue;
+		}
+		for e in &chart.errors {
+			let (line, col) = line_col(&chart.raw, e.span().start);
+			println!(
+				"{}: inote_{} {}:{}: error[parse]: {}",
+				args.input.display(),
+				i,
+				line + 1,
+				col + 1,
+				e
+			);
+			errors += 1;
+		}
+		for l in lint(chart, &config) {
+			let (line, col) = line_col(&chart.raw, l.span.start);
+			println!("{}: inote_{} {}:{}: {}", args.input.display(), i, line + 1, col + 1, l);
+			if l.severity == Severity::Error {
+				errors += 1;
+			}
+		}
+	}
+
+	if errors > 0 {
+		bail!("found {} errors", errors);
+	}
+	Ok(())
+}
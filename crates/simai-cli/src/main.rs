@@ -0,0 +1,122 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, bail};
+use clap::{Parser, Subcommand};
+use simai::parse::{Chart, Simai};
+
+mod autoplay;
+mod diff;
+mod easy;
+mod export;
+mod fingerprint;
+mod hitsounds;
+mod import;
+mod judge;
+mod lint;
+mod patterns;
+mod preview;
+mod rate;
+mod render;
+mod search;
+mod sheet;
+mod stats;
+
+#[derive(Parser)]
+#[command(name = "simai", version, about = "Tools for simai charts")]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Print the presses and releases of a perfect play of a chart
+	Autoplay(autoplay::Args),
+	/// Show the note changes between two versions of a chart
+	Diff(diff::Args),
+	/// Derive an easier practice chart from a chart
+	Easy(easy::Args),
+	/// Export a chart to another format
+	Export(export::Args),
+	/// Print content hashes of the charts of a maidata file
+	Fingerprint(fingerprint::Args),
+	/// Render the hitsounds of a chart to a WAV file, optionally over its music
+	Hitsounds(hitsounds::Args),
+	/// Import a .ma2 or osu!mania chart as maidata
+	Import(import::Args),
+	/// Judge recorded inputs against a chart and print the scorecard
+	Judge(judge::Args),
+	/// Check charts for likely mistakes
+	Lint(lint::Args),
+	/// Find stairs, trills, jacks and other patterns in a chart
+	Patterns(patterns::Args),
+	/// Print a chart as text, a row per tick
+	Preview(preview::Args),
+	/// Change the playback rate of a maidata file
+	Rate(rate::Args),
+	/// Draw the playfield at a moment of a chart as SVG
+	Render(render::Args),
+	/// Find charts in a directory of maidata files
+	Search(search::Args),
+	/// Draw a chart as a printable lane sheet
+	Sheet(sheet::Args),
+	/// Show note density and an estimate of the difficulty of a chart
+	Stats(stats::Args),
+}
+
+fn main() -> anyhow::Result<()> {
+	match Cli::parse().command {
+		Command::Autoplay(args) => autoplay::run(args),
+		Command::Diff(args) => diff::run(args),
+		Command::Easy(args) => easy::run(args),
+		Command::Export(args) => export::run(args),
+		Command::Fingerprint(args) => fingerprint::run(args),
+		Command::Hitsounds(args) => hitsounds::run(args),
+		Command::Import(args) => import::run(args),
+		Command::Judge(args) => judge::run(args),
+		Command::Lint(args) => lint::run(args),
+		Command::Patterns(args) => patterns::run(args),
+		Command::Preview(args) => preview::run(args),
+		Command::Rate(args) => rate::run(args),
+		Command::Render(args) => render::run(args),
+		Command::Search(args) => search::run(args),
+		Command::Sheet(args) => sheet::run(args),
+		Command::Stats(args) => stats::run(args),
+	}
+}
+
+/// Reads a maidata file, refusing charts with parse errors since writing them back would drop
+/// the broken parts.
+pub fn read_simai(path: &Path) -> anyhow::Result<Simai> {
+	let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+	let simai: Simai = text.parse()?;
+	for (i, chart) in simai.chart.iter().enumerate() {
+		if let Some(chart) = chart
+			&& !chart.errors.is_empty()
+		{
+			bail!("{}: inote_{} has {} parse errors", path.display(), i, chart.errors.len());
+		}
+	}
+	Ok(simai)
+}
+
+/// Reads the `inote_N` chart of a maidata file.
+pub fn read_chart(path: &Path, level: usize) -> anyhow::Result<Chart> {
+	let mut simai = read_simai(path)?;
+	simai
+		.chart
+		.get_mut(level)
+		.and_then(Option::take)
+		.with_context(|| format!("no inote_{} in {}", level, path.display()))
+}
+
+/// Writes to `path`, or to stdout if there is none.
+pub fn write_output(path: Option<&Path>, text: &str) -> anyhow::Result<()> {
+	match path {
+		Some(path) => fs::write(path, text).with_context(|| format!("writing {}", path.display())),
+		None => {
+			print!("{}", text);
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use simai::{
+	analysis::{PatternKind, Thresholds, patterns},
+	parse::line_col,
+	timeline::Timeline,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Only show these patterns, e.g. `trill` or `spin-slide`
+	#[arg(short, long)]
+	kind: Vec<String>,
+	/// Only show occurrences with at least this many notes
+	#[arg(short, long, default_value_t = 0)]
+	min_notes: usize,
+	/// Longest gap in seconds between notes of the same pattern
+	#[arg(long, default_value_t = Thresholds::default().max_gap)]
+	max_gap: f64,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let kinds = args
+		.kind
+		.iter()
+		.map(|k| PatternKind::from_name(k).with_context(|| format!("unknown pattern: {}", k)))
+		.collect::<anyhow::Result<Vec<_>>>()?;
+	let chart = crate::read_chart(&args.file, args.level)?;
+	let thresholds = Thresholds { max_gap: args.max_gap, ..Thresholds::default() };
+
+	for p in patterns(&Timeline::from_chart(&chart), &thresholds) {
+		if (!kinds.is_empty() && !kinds.contains(&p.kind)) || p.notes < args.min_notes {
+			continue;
+		}
+		let (line, col) = line_col(&chart.raw, p.spans[0].start);
+		println!(
+			"{}:{}: {} of {} notes, {:.2}s-{:.2}s",
+			line + 1,
+			col + 1,
+			p.kind,
+			p.notes,
+			p.start,
+			p.end
+		);
+	}
+	Ok(())
+}
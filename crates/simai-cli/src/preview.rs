@@ -0,0 +1,30 @@
+use std::{ops::RangeInclusive, path::PathBuf};
+
+use simai::render::Preview;
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Only print these measures, e.g. `12` or `12-16`
+	#[arg(short, long, value_parser = parse_measures)]
+	measures: Option<RangeInclusive<usize>>,
+}
+
+fn parse_measures(s: &str) -> Result<RangeInclusive<usize>, String> {
+	let number = |s: &str| s.trim().parse::<usize>().map_err(|_| format!("'{}' is not a measure", s));
+	match s.split_once('-') {
+		Some((from, to)) => Ok(number(from)?..=number(to)?),
+		None => number(s).map(|m| m..=m),
+	}
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let chart = crate::read_chart(&args.file, args.level)?;
+	let preview = Preview { measures: args.measures };
+	print!("{}", preview.render(chart.notes.iter().flatten().map(|i| &i.inner)));
+	Ok(())
+}
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+use anyhow::ensure;
+use simai::transform::Rate;
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file to convert
+	input: PathBuf,
+	/// Playback rate, e.g. 0.75 or 1.25
+	#[arg(short, long)]
+	rate: f64,
+	/// Where to write the result, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	ensure!(args.rate > 0., "rate must be positive");
+	let simai = crate::read_simai(&args.input)?;
+	let simai = Rate(args.rate).simai(&simai);
+	crate::write_output(args.output.as_deref(), &simai.to_string())
+}
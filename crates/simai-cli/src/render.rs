@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use anyhow::ensure;
+use simai::{render::Playfield, timeline::Timeline};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Seconds since the start of the chart
+	#[arg(short, long)]
+	time: f64,
+	/// Width and height of the image in pixels
+	#[arg(short, long, default_value_t = Playfield::default().size)]
+	size: f64,
+	/// Seconds notes are shown before they arrive
+	#[arg(short, long, default_value_t = Playfield::default().approach)]
+	approach: f64,
+	/// Where to write the SVG, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	ensure!(args.approach > 0. && args.approach.is_finite(), "approach must be positive");
+	let timeline = Timeline::from_chart(&crate::read_chart(&args.file, args.level)?);
+	let playfield = Playfield { size: args.size, approach: args.approach };
+	crate::write_output(args.output.as_deref(), &playfield.render(&timeline, args.time))
+}
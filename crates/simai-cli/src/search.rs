@@ -0,0 +1,69 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Context;
+use simai::library::{Index, Query};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// Directory searched for maidata.txt files
+	root: PathBuf,
+	/// Filter such as `level>=13 && bpm>180 && slides>200`
+	query: String,
+	/// Index file, kept up to date on every search [default: ROOT/.simai-index.json]
+	#[arg(short, long)]
+	index: Option<PathBuf>,
+	/// Print the matching charts as JSON
+	#[arg(long)]
+	json: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let query: Query =
+		args.query.parse().with_context(|| format!("parsing query {:?}", args.query))?;
+	let index_path = args.index.unwrap_or_else(|| args.root.join(".simai-index.json"));
+
+	let mut index = match fs::read_to_string(&index_path) {
+		Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+			eprintln!("warning: rebuilding {}: {}", index_path.display(), e);
+			Index::default()
+		}),
+		Err(_) => Index::default(),
+	};
+	let refresh =
+		index.refresh(&args.root).with_context(|| format!("reading {}", args.root.display()))?;
+	for (path, e) in &refresh.failed {
+		eprintln!("warning: skipping {}: {}", path.display(), e);
+	}
+	if refresh.added + refresh.updated + refresh.removed > 0 {
+		fs::write(&index_path, serde_json::to_string(&index)?)
+			.with_context(|| format!("writing {}", index_path.display()))?;
+	}
+
+	let results = index.search(&query);
+	if args.json {
+		let results: Vec<_> = results
+			.iter()
+			.map(|(song, chart)| {
+				serde_json::json!({
+					"path": song.path,
+					"title": song.title,
+					"artist": song.artist,
+					"chart": chart,
+				})
+			})
+			.collect();
+		println!("{}", serde_json::to_string_pretty(&results)?);
+		return Ok(());
+	}
+	for (song, chart) in results {
+		println!(
+			"{} inote_{} lv {}: {} / {}",
+			song.path.display(),
+			chart.difficulty,
+			chart.level.as_deref().unwrap_or("?"),
+			song.title.as_deref().unwrap_or("?"),
+			song.artist.as_deref().unwrap_or("?")
+		);
+	}
+	Ok(())
+}
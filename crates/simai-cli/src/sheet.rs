@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use simai::render::Sheet;
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Write an HTML page instead of a bare SVG
+	#[arg(long)]
+	html: bool,
+	/// Pixels per measure
+	#[arg(short, long, default_value_t = Sheet::default().measure_height)]
+	measure_height: f64,
+	/// Where to write the sheet, stdout if omitted
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	let mut simai = crate::read_simai(&args.file)?;
+	let chart = simai
+		.chart
+		.get_mut(args.level)
+		.and_then(Option::take)
+		.with_context(|| format!("no inote_{} in {}", args.level, args.file.display()))?;
+	let sheet = Sheet { measure_height: args.measure_height, ..Sheet::default() };
+	let text = if args.html {
+		let title = simai.title.as_deref().unwrap_or("chart");
+		sheet.render_html(&chart, &format!("{} inote_{}", title, args.level))
+	} else {
+		sheet.render(&chart)
+	};
+	crate::write_output(args.output.as_deref(), &text)
+}
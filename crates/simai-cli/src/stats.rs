@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::ensure;
+use simai::{analysis::Density, timeline::Timeline};
+
+#[derive(clap::Args)]
+pub struct Args {
+	/// The maidata file
+	file: PathBuf,
+	/// Difficulty index of the chart, as in `inote_N`
+	#[arg(short, long)]
+	level: usize,
+	/// Seconds each density sample covers
+	#[arg(short, long, default_value_t = 1.)]
+	window: f64,
+	/// Seconds between density samples
+	#[arg(short, long, default_value_t = 0.25)]
+	step: f64,
+	/// Print the density series, runs and summary as JSON
+	#[arg(long)]
+	json: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<()> {
+	ensure!(args.window > 0., "window must be positive");
+	ensure!(args.step > 0., "step must be positive");
+	let timeline = Timeline::from_chart(&crate::read_chart(&args.file, args.level)?);
+	let density = Density::analyze(&timeline, args.window, args.step);
+
+	if args.json {
+		println!("{}", serde_json::to_string_pretty(&density)?);
+		return Ok(());
+	}
+	let s = &density.summary;
+	println!("notes: {}", s.notes);
+	println!("duration: {:.2}s", s.duration);
+	println!("average nps: {:.2}", s.average_nps);
+	println!("peak nps: {:.2} at {:.2}s", s.peak_nps, s.peak_time);
+	println!("peak slide load: {:.2}", s.peak_slide_load);
+	for run in &density.runs {
+		println!("{} of {} notes: {:.2}s-{:.2}s", run.kind, run.notes, run.start, run.end);
+	}
+	println!("difficulty: {:.2}", s.difficulty);
+	Ok(())
+}
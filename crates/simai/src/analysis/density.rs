@@ -0,0 +1,288 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::{chains, jack_step, singles, trill_step};
+use crate::def::*;
+use crate::layout::Point;
+use crate::timeline::{SIMULTANEOUS, Timeline};
+
+/// Longest gap in seconds between two notes of a jack, trill or stream.
+pub const RUN_GAP: f64 = 0.3;
+
+/// Most samples in a density series; longer charts get the first this many.
+pub const MAX_SAMPLES: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Sample {
+	pub time: f64,
+	/// Notes per second in the window around `time`.
+	pub nps: f64,
+	/// Slide distance per second in the window, in key ring radii.
+	pub slide_load: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+	/// The same key again and again.
+	Jack,
+	/// Two keys in alternation.
+	Trill,
+	/// Evenly spaced notes, whatever their keys.
+	Stream,
+}
+
+impl Display for RunKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RunKind::Jack => write!(f, "jack"),
+			RunKind::Trill => write!(f, "trill"),
+			RunKind::Stream => write!(f, "stream"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Run {
+	pub kind: RunKind,
+	pub start: f64,
+	pub end: f64,
+	pub notes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+	pub notes: usize,
+	/// Seconds from the first note to the end of the last.
+	pub duration: f64,
+	pub average_nps: f64,
+	pub peak_nps: f64,
+	pub peak_time: f64,
+	pub peak_slide_load: f64,
+	/// A heuristic, not calibrated against official levels: the 90th percentile NPS, plus half
+	/// the 90th percentile slide load, plus twice the share of notes in runs.
+	pub difficulty: f64,
+}
+
+/// Density graph and difficulty estimate of a chart.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Density {
+	pub series: Vec<Sample>,
+	pub runs: Vec<Run>,
+	pub summary: Summary,
+}
+
+/// How many notes an item counts as: one per tap, hold or touch, and for a slide one for the
+/// star plus one per track.
+pub fn note_count(item: &Item) -> usize {
+	match item {
+		Item::Tap(_) | Item::Hold(_) | Item::TouchTap(_) | Item::TouchHold(_) => 1,
+		Item::Slide(slide) => {
+			let star = slide.star_style.bits() & (REMOVE | SUDDEN) == 0;
+			star as usize + slide.tracks.len()
+		}
+		_ => 0,
+	}
+}
+
+impl SlideTrack {
+	/// Path of the star through the centers of the judgment areas, starting from `start`.
+	/// Impossible segments are taken as straight lines between their keys.
+	pub fn points(&self, start: Key) -> Vec<Point> {
+		let mut points = vec![];
+		let mut from = start;
+		for (shape, to) in self.path() {
+			match crate::slide::segment_areas(shape, from, to) {
+				Some(areas) => points.extend(areas.iter().map(|a| a[0].center())),
+				None => points.extend([from.pos(), to.pos()]),
+			}
+			from = to;
+		}
+		points
+	}
+
+	/// Length of [`SlideTrack::points`] in key ring radii.
+	pub fn distance(&self, start: Key) -> f64 {
+		self.points(start).windows(2).map(|w| w[0].dist(w[1])).sum()
+	}
+}
+
+// (start, end, distance) of every slide track
+fn slides(timeline: &Timeline) -> Vec<(f64, f64, f64)> {
+	let mut v = vec![];
+	for note in &timeline.notes {
+		let Item::Slide(slide) = &note.item else { continue };
+		for track in &slide.tracks {
+			let start = note.time + track.wait_secs(note.bpm);
+			let end = start + track.segment_secs(note.bpm).iter().sum::<f64>();
+			v.push((start, end, track.distance(slide.key)));
+		}
+	}
+	v
+}
+
+fn runs_by(
+	notes: &[(f64, Key)],
+	kind: RunKind,
+	min: usize,
+	step: impl Fn(Option<(f64, Key)>, (f64, Key), (f64, Key)) -> bool,
+) -> Vec<Run> {
+	chains(notes, |n| n.0, RUN_GAP, min, step)
+		.into_iter()
+		.map(|r| Run { kind, start: notes[r.start].0, end: notes[r.end - 1].0, notes: r.len() })
+		.collect()
+}
+
+/// Jacks of 3 notes or more, trills of 4 or more and streams of 8 or more.
+pub fn runs(timeline: &Timeline) -> Vec<Run> {
+	let notes: Vec<(f64, Key)> = singles(timeline).iter().map(|(n, k)| (n.time, *k)).collect();
+	let keys = |step: fn(Option<Key>, Key, Key) -> bool| {
+		move |z: Option<(f64, Key)>, a: (f64, Key), b: (f64, Key)| step(z.map(|z| z.1), a.1, b.1)
+	};
+	let mut runs = runs_by(&notes, RunKind::Jack, 3, keys(jack_step));
+	runs.extend(runs_by(&notes, RunKind::Trill, 4, keys(trill_step)));
+	runs.extend(runs_by(&notes, RunKind::Stream, 8, |z, a, b| {
+		let gap = b.0 - a.0;
+		z.is_none_or(|z| {
+			let prev = a.0 - z.0;
+			gap < prev * 1.25 && prev < gap * 1.25
+		})
+	}));
+	runs.sort_by(|a, b| a.start.total_cmp(&b.start));
+	runs
+}
+
+// share of `total` notes that are part of a run, counting notes in overlapping runs once
+fn run_share(timeline: &Timeline, runs: &[Run], total: usize) -> f64 {
+	if runs.is_empty() {
+		return 0.;
+	}
+	let covered = singles(timeline)
+		.iter()
+		.filter(|(n, _)| runs.iter().any(|r| r.start <= n.time && n.time <= r.end))
+		.count();
+	covered as f64 / total.max(1) as f64
+}
+
+fn percentile(values: &[f64], p: f64) -> f64 {
+	let mut v = values.to_vec();
+	v.sort_by(f64::total_cmp);
+	v.get(((v.len() as f64 - 1.) * p).round() as usize).copied().unwrap_or(0.)
+}
+
+impl Density {
+	/// Samples every `step` seconds, each over `window` seconds centered on it. The series is
+	/// empty unless both are positive. Notes never reached, e.g. after a BPM of 0, are left out.
+	pub fn analyze(timeline: &Timeline, window: f64, step: f64) -> Self {
+		let notes: Vec<(f64, usize)> =
+			timeline.reachable().map(|n| (n.time, note_count(&n.item))).filter(|(_, c)| *c > 0).collect();
+		let slides = slides(timeline);
+		let total: usize = notes.iter().map(|(_, c)| c).sum();
+		let first = notes.first().map_or(0., |n| n.0);
+		let last = timeline.reachable().map(|n| n.end_time()).fold(first, f64::max);
+
+		let samples = match step > 0. && window > 0. {
+			true => (((last - first) / step - 1e-9).ceil().max(0.) as usize + 1).min(MAX_SAMPLES),
+			false => 0,
+		};
+		let mut series = vec![];
+		for i in 0..samples {
+			let t = first + i as f64 * step;
+			let (lo, hi) = (t - window / 2., t + window / 2.);
+			let count: usize =
+				notes.iter().filter(|(time, _)| lo <= *time && *time < hi).map(|(_, c)| c).sum();
+			// slides count with the part of their movement inside the window
+			let distance: f64 = slides
+				.iter()
+				.map(|(start, end, dist)| {
+					let overlap = (end.min(hi) - start.max(lo)).max(0.);
+					if end - start > SIMULTANEOUS { dist * overlap / (end - start) } else { 0. }
+				})
+				.sum();
+			series.push(Sample { time: t, nps: count as f64 / window, slide_load: distance / window });
+		}
+
+		let runs = runs(timeline);
+		let peak = series.iter().copied().max_by(|a, b| a.nps.total_cmp(&b.nps));
+		let nps: Vec<f64> = series.iter().map(|s| s.nps).collect();
+		let load: Vec<f64> = series.iter().map(|s| s.slide_load).collect();
+		let in_runs = run_share(timeline, &runs, total);
+		let duration = last - first;
+
+		let summary = Summary {
+			notes: total,
+			duration,
+			average_nps: if duration > SIMULTANEOUS { total as f64 / duration } else { 0. },
+			peak_nps: peak.map_or(0., |p| p.nps),
+			peak_time: peak.map_or(0., |p| p.time),
+			peak_slide_load: load.iter().copied().fold(0., f64::max),
+			difficulty: percentile(&nps, 0.9) + 0.5 * percentile(&load, 0.9) + 2. * in_runs.min(1.),
+		};
+		Density { series, runs, summary }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn timeline(text: &str) -> Timeline {
+		Timeline::from_chart(&text.parse::<Chart>().unwrap())
+	}
+
+	#[test]
+	fn test_distance() {
+		let track = |s: &str| {
+			let Item::Slide(slide) = &timeline(&format!("(120){{4}}{},E", s)).notes[0].item else {
+				panic!()
+			};
+			slide.tracks[0].distance(slide.key)
+		};
+		// straight through the center, from A1 to A5
+		assert!(
+			(track("1-5[4:1]") - 2. * Sensor::new(SensorGroup::A, Key::K1).center().radius()).abs()
+				< 1e-9
+		);
+		assert!(track("1>5[4:1]") > track("1-5[4:1]"));
+	}
+
+	#[test]
+	fn test_runs() {
+		let kinds =
+			|text: &str| runs(&timeline(text)).iter().map(|r| (r.kind, r.notes)).collect::<Vec<_>>();
+		assert_eq!(kinds("(120){8}1,1,1,2,1,2,1,E"), [(RunKind::Jack, 3), (RunKind::Trill, 5)]);
+		assert_eq!(kinds("(120){8}1,2,3,4,5,6,7,8,1/5,E"), [(RunKind::Stream, 8)]);
+		assert!(kinds("(120){2}1,1,1,E").is_empty());
+	}
+
+	#[test]
+	fn test_run_share() {
+		let share = |text: &str, total| {
+			let timeline = timeline(text);
+			run_share(&timeline, &runs(&timeline), total)
+		};
+		// the jack and the trill share a note
+		assert_eq!(share("(120){8}1,1,1,2,1,2,1,E", 7), 1.);
+		// the trill is also a stream
+		assert_eq!(share("(120){8}1,2,1,2,1,2,1,2,E", 8), 1.);
+		assert_eq!(share("(120){8}1,2,1,2,1,2,1,2,,,,3,E", 9), 8. / 9.);
+		assert_eq!(share("(120){2}1,1,1,E", 3), 0.);
+	}
+
+	#[test]
+	fn test_density() {
+		let d = Density::analyze(&timeline("(120){4}1,2,3,4,1-5[4:1],,,,E"), 1., 0.5);
+		assert_eq!(d.summary.notes, 6);
+		// the slide at 2s counts twice, with the tap before it
+		assert_eq!(d.summary.peak_nps, 3.);
+		assert!(d.series.iter().any(|s| s.slide_load > 0.));
+		assert_eq!(d.series.first().map(|s| s.time), Some(0.));
+
+		// the second note never comes
+		let d = Density::analyze(&timeline("(0){4}1,2,E"), 1., 0.25);
+		assert_eq!((d.summary.notes, d.series.len()), (1, 1));
+		assert!(Density::analyze(&timeline("(120){4}1,E"), 1., 0.).series.is_empty());
+	}
+}
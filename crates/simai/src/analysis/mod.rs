@@ -0,0 +1,7 @@
+mod density;
+mod pattern;
+mod playability;
+
+pub use density::*;
+pub use pattern::*;
+pub use playability::*;
@@ -0,0 +1,304 @@
+use std::{fmt::Display, ops::Range};
+
+use serde::Serialize;
+
+use crate::def::*;
+use crate::parse::SimpleSpan;
+use crate::timeline::{SIMULTANEOUS, TimedNote, Timeline};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+	/// Single notes walking around the ring one key at a time.
+	Stairs,
+	Trill,
+	Jack,
+	/// Pairs of simultaneous button notes in a row.
+	EachDoubles,
+	/// A slide going at least once around the center.
+	SpinSlide,
+	TouchStream,
+	BreakChain,
+	/// Taps on other keys while a hold is held.
+	HoldTap,
+}
+
+impl PatternKind {
+	pub const ALL: [PatternKind; 8] = [
+		PatternKind::Stairs,
+		PatternKind::Trill,
+		PatternKind::Jack,
+		PatternKind::EachDoubles,
+		PatternKind::SpinSlide,
+		PatternKind::TouchStream,
+		PatternKind::BreakChain,
+		PatternKind::HoldTap,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			PatternKind::Stairs => "stairs",
+			PatternKind::Trill => "trill",
+			PatternKind::Jack => "jack",
+			PatternKind::EachDoubles => "each-doubles",
+			PatternKind::SpinSlide => "spin-slide",
+			PatternKind::TouchStream => "touch-stream",
+			PatternKind::BreakChain => "break-chain",
+			PatternKind::HoldTap => "hold-tap",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<PatternKind> {
+		PatternKind::ALL.into_iter().find(|k| k.name() == name)
+	}
+}
+
+impl Display for PatternKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+/// When notes make up a pattern. Counts are the least number of notes for an occurrence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+	/// Longest gap in seconds between two notes of the same stairs, trill, jack, chain or stream.
+	pub max_gap: f64,
+	pub stairs: usize,
+	pub trill: usize,
+	pub jack: usize,
+	/// Pairs, not notes.
+	pub each_doubles: usize,
+	/// Turns around the center.
+	pub spin: f64,
+	pub touch_stream: usize,
+	pub break_chain: usize,
+	/// Taps during the hold, not counting the hold.
+	pub hold_taps: usize,
+}
+
+impl Default for Thresholds {
+	fn default() -> Self {
+		Thresholds {
+			max_gap: 0.3,
+			stairs: 4,
+			trill: 4,
+			jack: 3,
+			each_doubles: 3,
+			spin: 1.,
+			touch_stream: 4,
+			break_chain: 3,
+			hold_taps: 2,
+		}
+	}
+}
+
+/// One occurrence of a pattern, from the first note arriving until the last one ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+	pub kind: PatternKind,
+	pub start: f64,
+	pub end: f64,
+	pub notes: usize,
+	pub spans: Vec<SimpleSpan>,
+}
+
+impl Pattern {
+	fn new(kind: PatternKind, notes: &[&TimedNote]) -> Self {
+		Pattern {
+			kind,
+			start: notes.iter().map(|n| n.time).fold(f64::INFINITY, f64::min),
+			end: notes.iter().map(|n| n.end_time()).fold(f64::NEG_INFINITY, f64::max),
+			notes: notes.len(),
+			spans: notes.iter().map(|n| n.span).collect(),
+		}
+	}
+}
+
+/// Index ranges of the maximal chains of at least `min` elements of `v`, where each element is
+/// at most `max_gap` seconds after the previous one and `step` accepts it. `step` sees the
+/// element two back if it is in the chain, the previous element and the current one.
+pub(crate) fn chains<T: Copy>(
+	v: &[T],
+	time: impl Fn(T) -> f64,
+	max_gap: f64,
+	min: usize,
+	step: impl Fn(Option<T>, T, T) -> bool,
+) -> Vec<Range<usize>> {
+	let mut chains = vec![];
+	let mut start = 0;
+	for i in 1..=v.len() {
+		let close = i < v.len() && time(v[i]) - time(v[i - 1]) <= max_gap;
+		if !(close && step((i >= start + 2).then(|| v[i - 2]), v[i - 1], v[i])) {
+			if i - start >= min {
+				chains.push(start..i);
+			}
+			// the previous element may still start a chain of its own with this one
+			start = if close && step(None, v[i - 1], v[i]) { i - 1 } else { i };
+		}
+	}
+	chains
+}
+
+/// Whether `b` continues a jack: the same key again.
+pub(crate) fn jack_step(_: Option<Key>, a: Key, b: Key) -> bool {
+	a == b
+}
+
+/// Whether `b` continues a trill: two keys in alternation.
+pub(crate) fn trill_step(z: Option<Key>, a: Key, b: Key) -> bool {
+	a != b && z.is_none_or(|z| z == b)
+}
+
+/// The key a note is pressed on when it arrives. Slides without a star have none.
+pub(crate) fn pressed_key(item: &Item) -> Option<Key> {
+	match item {
+		Item::Tap(tap) => Some(tap.key),
+		Item::Hold(hold) => Some(hold.key),
+		Item::Slide(slide) if slide.star_style.bits() & (REMOVE | SUDDEN) == 0 => Some(slide.key),
+		_ => None,
+	}
+}
+
+/// Button notes grouped by time.
+fn button_groups(timeline: &Timeline) -> Vec<Vec<(&TimedNote, Key)>> {
+	let mut groups: Vec<Vec<(&TimedNote, Key)>> = vec![];
+	for note in &timeline.notes {
+		let Some(key) = pressed_key(&note.item) else { continue };
+		match groups.last_mut() {
+			Some(group) if (group[0].0.time - note.time).abs() < SIMULTANEOUS => group.push((note, key)),
+			_ => groups.push(vec![(note, key)]),
+		}
+	}
+	groups
+}
+
+/// Button notes with no other button note at the same time.
+pub(crate) fn singles(timeline: &Timeline) -> Vec<(&TimedNote, Key)> {
+	button_groups(timeline).into_iter().filter(|g| g.len() == 1).map(|g| g[0]).collect()
+}
+
+fn is_break(item: &Item) -> bool {
+	match item {
+		Item::Tap(tap) => tap.style.bits() & BREAK != 0,
+		Item::Hold(hold) => hold.style.bits() & BREAK != 0,
+		Item::Slide(slide) => {
+			slide.star_style.bits() & BREAK != 0
+				|| slide.tracks.iter().any(|t| t.style().bits() & BREAK != 0)
+		}
+		_ => false,
+	}
+}
+
+impl SlideTrack {
+	/// Turns the star makes around the center, positive clockwise.
+	pub fn turns(&self, start: Key) -> f64 {
+		// points near the center have no meaningful angle
+		let angles: Vec<f64> =
+			self.points(start).iter().filter(|p| p.radius() > 0.2).map(|p| p.angle()).collect();
+		let degrees: f64 =
+			angles.windows(2).map(|w| (w[1] - w[0] + 180.).rem_euclid(360.) - 180.).sum();
+		degrees / 360.
+	}
+}
+
+fn chain_patterns(
+	kind: PatternKind,
+	notes: &[(&TimedNote, Key)],
+	max_gap: f64,
+	min: usize,
+	step: impl Fn(Option<Key>, Key, Key) -> bool,
+) -> Vec<Pattern> {
+	chains(notes, |n| n.0.time, max_gap, min, |z, a, b| step(z.map(|z| z.1), a.1, b.1))
+		.into_iter()
+		.map(|r| Pattern::new(kind, &notes[r].iter().map(|n| n.0).collect::<Vec<_>>()))
+		.collect()
+}
+
+/// Every pattern occurrence in the chart, ordered by start time.
+pub fn patterns(timeline: &Timeline, thresholds: &Thresholds) -> Vec<Pattern> {
+	let t = thresholds;
+	let singles = singles(timeline);
+	let mut v = vec![];
+
+	v.extend(chain_patterns(PatternKind::Stairs, &singles, t.max_gap, t.stairs, |z, a, b| {
+		let step = a.offset(b);
+		(step == 1 || step == 7) && z.is_none_or(|z| z.offset(a) == step)
+	}));
+	v.extend(chain_patterns(PatternKind::Trill, &singles, t.max_gap, t.trill, trill_step));
+	v.extend(chain_patterns(PatternKind::Jack, &singles, t.max_gap, t.jack, jack_step));
+
+	let doubles: Vec<Vec<(&TimedNote, Key)>> =
+		button_groups(timeline).into_iter().filter(|g| g.len() == 2).collect();
+	let times: Vec<f64> = doubles.iter().map(|g| g[0].0.time).collect();
+	for r in chains(&times, |t| t, t.max_gap, t.each_doubles, |_, _, _| true) {
+		let notes: Vec<&TimedNote> = doubles[r].iter().flatten().map(|n| n.0).collect();
+		v.push(Pattern::new(PatternKind::EachDoubles, &notes));
+	}
+
+	for note in &timeline.notes {
+		let Item::Slide(slide) = &note.item else { continue };
+		if slide.tracks.iter().any(|track| track.turns(slide.key).abs() >= t.spin - 1e-6) {
+			v.push(Pattern::new(PatternKind::SpinSlide, &[note]));
+		}
+	}
+
+	let touches: Vec<&TimedNote> = timeline
+		.notes
+		.iter()
+		.filter(|n| matches!(n.item, Item::TouchTap(_) | Item::TouchHold(_)))
+		.collect();
+	for r in chains(&touches, |n| n.time, t.max_gap, t.touch_stream, |_, _, _| true) {
+		v.push(Pattern::new(PatternKind::TouchStream, &touches[r]));
+	}
+
+	let breaks: Vec<&TimedNote> = timeline.notes.iter().filter(|n| is_break(&n.item)).collect();
+	for r in chains(&breaks, |n| n.time, t.max_gap, t.break_chain, |_, _, _| true) {
+		v.push(Pattern::new(PatternKind::BreakChain, &breaks[r]));
+	}
+
+	for hold in &timeline.notes {
+		let Item::Hold(Hold { key, .. }) = &hold.item else { continue };
+		let mut notes = vec![hold];
+		notes.extend(timeline.notes.iter().filter(|n| {
+			pressed_key(&n.item).is_some_and(|k| k != *key)
+				&& hold.time + SIMULTANEOUS < n.time
+				&& n.time < hold.end_time() - SIMULTANEOUS
+		}));
+		if notes.len() > t.hold_taps.max(1) {
+			v.push(Pattern::new(PatternKind::HoldTap, &notes));
+		}
+	}
+
+	v.sort_by(|a, b| a.start.total_cmp(&b.start));
+	v
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn found(text: &str) -> Vec<(PatternKind, usize)> {
+		let timeline = Timeline::from_chart(&text.parse::<Chart>().unwrap());
+		patterns(&timeline, &Thresholds::default()).iter().map(|p| (p.kind, p.notes)).collect()
+	}
+
+	#[test]
+	fn test_chains() {
+		let v = [0., 0.1, 0.2, 1., 1.1, 1.2, 1.3];
+		assert_eq!(chains(&v, |t| t, 0.15, 3, |_, _, _| true), [0..3, 3..7]);
+		assert_eq!(chains(&v, |t| t, 0.15, 4, |_, _, _| true), vec![3..7]);
+	}
+
+	#[test]
+	fn test_patterns() {
+		assert_eq!(found("(120){8}1,2,3,4,8,E"), [(PatternKind::Stairs, 4)]);
+		assert_eq!(found("(120){8}1,5,1,5,3,3,3,E"), [(PatternKind::Trill, 4), (PatternKind::Jack, 3)]);
+		assert_eq!(found("(120){8}1/5,2/6,3/7,E"), [(PatternKind::EachDoubles, 6)]);
+		assert_eq!(found("(120){4}1>1[1:1],1-5[4:1],E"), [(PatternKind::SpinSlide, 1)]);
+		assert_eq!(found("(120){8}A1,B2,C,E3,E"), [(PatternKind::TouchStream, 4)]);
+		assert_eq!(found("(120){8}1b,5b,3b,E"), [(PatternKind::BreakChain, 3)]);
+		assert_eq!(found("(120){4}1h[1:1],3,5,,E"), [(PatternKind::HoldTap, 3)]);
+	}
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::def::*;
+use crate::parse::SimpleSpan;
+use crate::timeline::{SIMULTANEOUS, Timeline};
+
+/// How much a player can do at once.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+	pub hands: usize,
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Limits { hands: 2 }
+	}
+}
+
+/// Something the player has to touch, from `start` until `end`. Taps have `end == start`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Input {
+	start: f64,
+	end: f64,
+	sensor: Option<Sensor>,
+	span: SimpleSpan,
+}
+
+impl Input {
+	fn active(&self, t: f64) -> bool {
+		(self.start - t).abs() < SIMULTANEOUS
+			|| (self.start - SIMULTANEOUS <= t && t < self.end - SIMULTANEOUS)
+	}
+}
+
+fn inputs(timeline: &Timeline) -> Vec<Input> {
+	let mut v = vec![];
+	for note in &timeline.notes {
+		let input =
+			|start: f64, end: f64, sensor: Option<Sensor>| Input { start, end, sensor, span: note.span };
+		match &note.item {
+			Item::Tap(_) => v.push(input(note.time, note.time, None)),
+			Item::Hold(_) => v.push(input(note.time, note.end_time(), None)),
+			Item::TouchTap(touch) => v.push(input(note.time, note.time, Some(touch.sensor))),
+			Item::TouchHold(touch) => v.push(input(note.time, note.end_time(), Some(touch.sensor))),
+			Item::Slide(slide) => {
+				let mut star = slide.star_style.bits() & (REMOVE | SUDDEN) == 0;
+				for track in &slide.tracks {
+					let start = note.time + track.wait_secs(note.bpm);
+					let end = start + track.segment_secs(note.bpm).iter().sum::<f64>();
+					v.push(input(start, end, None));
+					// without a wait the finger on the star is the one sliding
+					star &= start - note.time > SIMULTANEOUS;
+				}
+				if star {
+					v.push(input(note.time, note.time, None));
+				}
+			}
+			_ => {}
+		}
+	}
+	v
+}
+
+/// The inputs needed at one moment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Load {
+	pub time: f64,
+	/// Hands needed: one per button, held or slid star, and one per group of adjacent touch
+	/// sensors.
+	pub hands: usize,
+	/// Notes being played at that moment.
+	pub spans: Vec<SimpleSpan>,
+}
+
+// number of groups of sensors connected through adjacency or being the same sensor
+fn touch_groups(sensors: &[Sensor]) -> usize {
+	let mut group: Vec<usize> = (0..sensors.len()).collect();
+	fn root(group: &mut [usize], i: usize) -> usize {
+		if group[i] == i { i } else { root(group, group[i]) }
+	}
+	for i in 0..sensors.len() {
+		for j in i + 1..sensors.len() {
+			if sensors[i] == sensors[j] || sensors[i].is_adjacent(&sensors[j]) {
+				let (a, b) = (root(&mut group, i), root(&mut group, j));
+				group[a] = b;
+			}
+		}
+	}
+	(0..sensors.len()).filter(|&i| root(&mut group, i) == i).count()
+}
+
+/// Hands needed at every moment an input starts.
+pub fn loads(timeline: &Timeline) -> Vec<Load> {
+	let inputs = inputs(timeline);
+	let mut times: Vec<f64> = inputs.iter().map(|i| i.start).collect();
+	times.sort_by(f64::total_cmp);
+	times.dedup_by(|a, b| (*a - *b).abs() < SIMULTANEOUS);
+
+	times
+		.into_iter()
+		.map(|time| {
+			let active: Vec<&Input> = inputs.iter().filter(|i| i.active(time)).collect();
+			let sensors: Vec<Sensor> = active.iter().filter_map(|i| i.sensor).collect();
+			let buttons = active.iter().filter(|i| i.sensor.is_none()).count();
+			let mut spans: Vec<SimpleSpan> = active.iter().map(|i| i.span).collect();
+			spans.sort_by_key(|s| s.start);
+			spans.dedup();
+			Load { time, hands: buttons + touch_groups(&sensors), spans }
+		})
+		.collect()
+}
+
+/// Moments needing more than `limits` allows.
+pub fn overloads(timeline: &Timeline, limits: &Limits) -> Vec<Load> {
+	loads(timeline).into_iter().filter(|l| l.hands > limits.hands).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn hands(text: &str) -> Vec<usize> {
+		let chart: Chart = text.parse().unwrap();
+		loads(&Timeline::from_chart(&chart)).iter().map(|l| l.hands).collect()
+	}
+
+	#[test]
+	fn test_loads() {
+		assert_eq!(hands("(120){4}1/5,1h[2:1],3,5,E"), [2, 1, 2, 1]);
+		// the star and its slide are played with one finger, but the slide then holds it
+		assert_eq!(hands("(120){4}1-5[4:1]/3,7,E"), [2, 2]);
+		assert_eq!(hands("(120){4}B1/B2/C/E1,A3/A7,E"), [1, 2]);
+	}
+
+	#[test]
+	fn test_overloads() {
+		let chart: Chart = "(120){4}1h[2:1]/5h[2:1],3,E".parse().unwrap();
+		let over = overloads(&Timeline::from_chart(&chart), &Limits::default());
+		assert_eq!(over.len(), 1);
+		assert_eq!(over[0].hands, 3);
+		assert_eq!(over[0].spans.len(), 3);
+	}
+}
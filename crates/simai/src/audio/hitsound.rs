@@ -0,0 +1,268 @@
+use std::f64::consts::TAU;
+use std::fmt::Display;
+
+use crate::def::*;
+use crate::timeline::Timeline;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Sound {
+	Tap,
+	Break,
+	Ex,
+	/// A slide track starting to move.
+	SlideStart,
+	Touch,
+	HoldRelease,
+	/// A metronome click on a beat.
+	Beat,
+	/// A metronome click on the first beat of a measure.
+	Downbeat,
+}
+
+impl Sound {
+	pub const ALL: [Sound; 8] = [
+		Sound::Tap,
+		Sound::Break,
+		Sound::Ex,
+		Sound::SlideStart,
+		Sound::Touch,
+		Sound::HoldRelease,
+		Sound::Beat,
+		Sound::Downbeat,
+	];
+
+	/// Frequencies in Hz with their loudness, how fast the sound fades in seconds, and how long
+	/// it lasts.
+	fn recipe(self) -> (&'static [(f64, f64)], f64, f64) {
+		match self {
+			Sound::Tap => (&[(880., 0.6), (1760., 0.2)], 0.03, 0.1),
+			Sound::Break => (&[(1320., 0.5), (1760., 0.4), (2640., 0.2)], 0.08, 0.25),
+			Sound::Ex => (&[(1175., 0.5), (2350., 0.3), (3525., 0.15)], 0.04, 0.12),
+			Sound::SlideStart => (&[(600., 0.5)], 0.06, 0.15),
+			Sound::Touch => (&[(1568., 0.5), (3136., 0.2)], 0.015, 0.06),
+			Sound::HoldRelease => (&[(660., 0.5)], 0.02, 0.06),
+			Sound::Beat => (&[(1000., 0.4)], 0.01, 0.04),
+			Sound::Downbeat => (&[(1500., 0.5)], 0.012, 0.05),
+		}
+	}
+
+	/// The sound as samples at `sample_rate`, peaking at about 1.
+	pub fn wave(self, sample_rate: u32) -> Vec<f32> {
+		let (partials, decay, length) = self.recipe();
+		let rate = sample_rate as f64;
+		(0..(length * rate) as usize)
+			.map(|i| {
+				let t = i as f64 / rate;
+				// slide starts rise by an octave so they stand out from taps
+				let bend = if self == Sound::SlideStart { 1. + t / length / 2. } else { 1. };
+				let tone: f64 = partials.iter().map(|(f, a)| a * (TAU * f * bend * t).sin()).sum();
+				// a few samples of attack keep the start from clicking
+				let attack = (t * rate / 32.).min(1.);
+				(tone * attack * (-t / decay).exp()) as f32
+			})
+			.collect()
+	}
+}
+
+impl Display for Sound {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Sound::Tap => "tap",
+			Sound::Break => "break",
+			Sound::Ex => "ex",
+			Sound::SlideStart => "slide start",
+			Sound::Touch => "touch",
+			Sound::HoldRelease => "hold release",
+			Sound::Beat => "beat",
+			Sound::Downbeat => "downbeat",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+/// Longest audio rendered, in seconds. Sounds after it are left out.
+pub const MAX_LENGTH: f64 = 3600.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+	/// Seconds since the start of the chart.
+	pub time: f64,
+	pub sound: Sound,
+}
+
+fn head(style: S) -> Sound {
+	if style & BREAK != 0 {
+		Sound::Break
+	} else if style & EX != 0 {
+		Sound::Ex
+	} else {
+		Sound::Tap
+	}
+}
+
+/// Every sound of a chart in time order, with a click on each beat if `metronome` is set.
+/// Sounds that never come, e.g. after a BPM of 0, are left out.
+pub fn hits(timeline: &Timeline, metronome: bool) -> Vec<Hit> {
+	let mut hits = vec![];
+	let mut hit = |time: f64, sound| {
+		if time.is_finite() {
+			hits.push(Hit { time, sound })
+		}
+	};
+	for note in timeline.reachable() {
+		match &note.item {
+			Item::Tap(tap) => hit(note.time, head(tap.style.bits())),
+			Item::Hold(hold) => {
+				hit(note.time, head(hold.style.bits()));
+				hit(note.end_time(), Sound::HoldRelease);
+			}
+			Item::Slide(slide) => {
+				let s = slide.star_style.bits();
+				if s & (REMOVE | SUDDEN) == 0 {
+					hit(note.time, head(s));
+				}
+				for track in &slide.tracks {
+					let sound =
+						if track.style().bits() & BREAK != 0 { Sound::Break } else { Sound::SlideStart };
+					hit(note.time + track.wait_secs(note.bpm), sound);
+				}
+			}
+			Item::TouchTap(_) => hit(note.time, Sound::Touch),
+			Item::TouchHold(_) => {
+				hit(note.time, Sound::Touch);
+				hit(note.end_time(), Sound::HoldRelease);
+			}
+			_ => {}
+		}
+	}
+	if metronome && !timeline.tempo.is_empty() {
+		let last = timeline.reachable().map(|n| n.end_time()).fold(0., f64::max);
+		let end = timeline.end.filter(|t| t.is_finite()).unwrap_or(0.).max(last).min(MAX_LENGTH);
+		let beats = (timeline.tempo.measure_at(end) * 4. + 1e-9).floor() as usize;
+		for beat in 0..=beats {
+			let sound = if beat % 4 == 0 { Sound::Downbeat } else { Sound::Beat };
+			hit(timeline.tempo.time_at(beat as f64 / 4.), sound);
+		}
+	}
+	hits.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.sound.cmp(&b.sound)));
+	hits
+}
+
+/// Renders the hitsounds of a chart as mono audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hitsounds {
+	pub sample_rate: u32,
+	/// Whether to click on every beat.
+	pub metronome: bool,
+	/// Loudness of the hitsounds, 1 for about full scale.
+	pub volume: f32,
+}
+
+impl Default for Hitsounds {
+	fn default() -> Self {
+		Hitsounds { sample_rate: 44100, metronome: false, volume: 0.5 }
+	}
+}
+
+impl Hitsounds {
+	/// Samples with every sound `offset` seconds after its time in the chart, which is where it
+	/// falls in the music for an `&first` of `offset`. Sounds that would start before 0 or after
+	/// [`MAX_LENGTH`] are left out.
+	pub fn render(&self, timeline: &Timeline, offset: f64) -> Vec<f32> {
+		let waves: Vec<Vec<f32>> = Sound::ALL.iter().map(|s| s.wave(self.sample_rate)).collect();
+		let mut out: Vec<f32> = vec![];
+		for hit in hits(timeline, self.metronome) {
+			let time = hit.time + offset;
+			if !(0. ..=MAX_LENGTH).contains(&time) {
+				continue;
+			}
+			let start = (time * self.sample_rate as f64).round() as usize;
+			let wave = &waves[hit.sound as usize];
+			if out.len() < start + wave.len() {
+				out.resize(start + wave.len(), 0.);
+			}
+			for (o, w) in out[start..].iter_mut().zip(wave) {
+				*o += w * self.volume;
+			}
+		}
+		out
+	}
+}
+
+/// Adds mono `sounds` to every channel of interleaved `music`, making it longer if needed.
+pub fn mix(music: &mut Vec<f32>, channels: usize, sounds: &[f32]) {
+	let channels = channels.max(1);
+	if music.len() < sounds.len() * channels {
+		music.resize(sounds.len() * channels, 0.);
+	}
+	for (frame, s) in music.chunks_mut(channels).zip(sounds) {
+		for sample in frame {
+			*sample += s;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn timeline(text: &str) -> Timeline {
+		Timeline::from_chart(&text.parse::<Chart>().unwrap())
+	}
+
+	#[test]
+	fn test_hits() {
+		let sounds = |text: &str, metronome| {
+			hits(&timeline(text), metronome).iter().map(|h| (h.time, h.sound)).collect::<Vec<_>>()
+		};
+		assert_eq!(
+			sounds("(120){4}1b,2x,3h[4:1],1-5[4:1],C,E", false),
+			vec![
+				(0., Sound::Break),
+				(0.5, Sound::Ex),
+				(1., Sound::Tap),
+				(1.5, Sound::Tap),
+				(1.5, Sound::HoldRelease),
+				(2., Sound::SlideStart),
+				(2., Sound::Touch),
+			]
+		);
+		assert_eq!(
+			sounds("(240){1}1?-5[4:1],E", true),
+			vec![
+				(0., Sound::Downbeat),
+				(0.25, Sound::SlideStart),
+				(0.25, Sound::Beat),
+				(0.5, Sound::Beat),
+				(0.75, Sound::Beat),
+				(1., Sound::Downbeat),
+			]
+		);
+	}
+
+	#[test]
+	fn test_render() {
+		let hitsounds = Hitsounds { sample_rate: 1000, ..Default::default() };
+		let out = hitsounds.render(&timeline("(120){4}1,1,E"), 1.);
+		let tap = Sound::Tap.wave(1000);
+		assert_eq!(out.len(), 1500 + tap.len());
+		assert!(out[..1000].iter().all(|s| *s == 0.));
+		assert_eq!(out[1010], tap[10] * 0.5);
+		// the first tap would come before the music starts
+		assert_eq!(hitsounds.render(&timeline("(120){4}1,1,E"), -0.25).len(), 250 + tap.len());
+		// the second tap never comes after a BPM of 0, and the third is too late
+		let out = hitsounds.render(&timeline("(0){4}1,1,E"), 1.);
+		assert_eq!(out.len(), 1000 + tap.len());
+		assert_eq!(hitsounds.render(&timeline("(120){#4000}1,1,E"), 0.).len(), tap.len());
+		let metronome = Hitsounds { metronome: true, ..hitsounds };
+		assert!(!metronome.render(&timeline("(120){4}1,(0)1h[4:1],E"), 0.).is_empty());
+	}
+
+	#[test]
+	fn test_mix() {
+		let mut music = vec![1., 2., 3., 4.];
+		mix(&mut music, 2, &[0.5, 0.5, 0.5]);
+		assert_eq!(music, vec![1.5, 2.5, 3.5, 4.5, 0.5, 0.5]);
+	}
+}
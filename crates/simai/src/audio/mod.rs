@@ -0,0 +1,3 @@
+mod hitsound;
+
+pub use hitsound::*;
@@ -0,0 +1,676 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::def::*;
+use crate::parse::Chart;
+use crate::timeline::{QuantizeError, Quantizer, TempoMap, Timeline};
+
+/// Ticks per measure of the arcade charts.
+pub const RESOLUTION: u32 = 384;
+
+/// Note variant, written as the first two letters of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Variant {
+	Normal,
+	Break,
+	Ex,
+	BreakEx,
+	/// A slide segment continuing the previous one.
+	Connect,
+}
+
+impl Variant {
+	fn prefix(self) -> &'static str {
+		match self {
+			Variant::Normal => "NM",
+			Variant::Break => "BR",
+			Variant::Ex => "EX",
+			Variant::BreakEx => "BX",
+			Variant::Connect => "CN",
+		}
+	}
+
+	fn from_prefix(s: &str) -> Option<Self> {
+		Some(match s {
+			"NM" => Variant::Normal,
+			"BR" => Variant::Break,
+			"EX" => Variant::Ex,
+			"BX" => Variant::BreakEx,
+			"CN" => Variant::Connect,
+			_ => return None,
+		})
+	}
+
+	fn from_style(s: S) -> Self {
+		match (s & BREAK != 0, s & EX != 0) {
+			(true, true) => Variant::BreakEx,
+			(true, false) => Variant::Break,
+			(false, true) => Variant::Ex,
+			(false, false) => Variant::Normal,
+		}
+	}
+
+	fn style(self) -> S {
+		match self {
+			Variant::Break => BREAK,
+			Variant::Ex => EX,
+			Variant::BreakEx => BREAK | EX,
+			Variant::Normal | Variant::Connect => NONE,
+		}
+	}
+}
+
+/// A single line of an ma2 file. Positions and lengths are in ticks since the start of the
+/// chart, at [`RESOLUTION`] ticks per measure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+	Bpm {
+		at: u32,
+		bpm: f64,
+	},
+	Met {
+		at: u32,
+		num: u32,
+		den: u32,
+	},
+	Tap {
+		at: u32,
+		key: Key,
+		variant: Variant,
+	},
+	Star {
+		at: u32,
+		key: Key,
+		variant: Variant,
+	},
+	Hold {
+		at: u32,
+		key: Key,
+		len: u32,
+		variant: Variant,
+	},
+	/// `code` is the three letter shape code, e.g. `SI_` or `SCL`.
+	Slide {
+		at: u32,
+		key: Key,
+		code: String,
+		wait: u32,
+		len: u32,
+		end: Key,
+		variant: Variant,
+	},
+	Touch {
+		at: u32,
+		sensor: Sensor,
+		firework: bool,
+	},
+	TouchHold {
+		at: u32,
+		sensor: Sensor,
+		len: u32,
+		firework: bool,
+	},
+}
+
+impl Record {
+	pub fn at(&self) -> u32 {
+		match self {
+			Record::Bpm { at, .. }
+			| Record::Met { at, .. }
+			| Record::Tap { at, .. }
+			| Record::Star { at, .. }
+			| Record::Hold { at, .. }
+			| Record::Slide { at, .. }
+			| Record::Touch { at, .. }
+			| Record::TouchHold { at, .. } => *at,
+		}
+	}
+}
+
+/// The note part of an ma2 file. Header lines other than the resolution, as well as the
+/// statistics at the end, are not kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ma2 {
+	pub records: Vec<Record>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ma2Error {
+	/// 1-based line number.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for Ma2Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for Ma2Error {}
+
+fn key(s: &str) -> Option<Key> {
+	let i = s.parse::<u8>().ok().filter(|i| *i < 8)?;
+	Some(Key::ALL[i as usize])
+}
+
+fn sensor(index: &str, group: &str) -> Option<Sensor> {
+	let group = match group {
+		"A" => SensorGroup::A,
+		"B" => SensorGroup::B,
+		"C" => return Some(Sensor::C),
+		"D" => SensorGroup::D,
+		"E" => SensorGroup::E,
+		_ => return None,
+	};
+	Some(Sensor::new(group, key(index)?))
+}
+
+impl FromStr for Ma2 {
+	type Err = Ma2Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut ma2 = Ma2::default();
+		let mut resolution = RESOLUTION;
+
+		for (i, line) in s.lines().enumerate() {
+			let f: Vec<&str> = line.split('\t').map(str::trim).collect();
+			let err = |message: &str| Ma2Error { line: i + 1, message: message.to_string() };
+			let int = |j: usize| -> Result<u32, Ma2Error> {
+				f.get(j).and_then(|s| s.parse().ok()).ok_or_else(|| err("expected an integer"))
+			};
+			let key = |j: usize| f.get(j).and_then(|s| key(s)).ok_or_else(|| err("invalid key"));
+			// positions and lengths are stored at the resolution of the file
+			let ticks = |t: u32| {
+				(t as u64 * RESOLUTION as u64)
+					.checked_div(resolution as u64)
+					.and_then(|t| u32::try_from(t).ok())
+					.ok_or_else(|| err("position out of range"))
+			};
+			let at = || -> Result<u32, Ma2Error> {
+				let (measure, tick) = (int(1)?, int(2)?);
+				let t = measure.checked_mul(resolution).and_then(|t| t.checked_add(tick));
+				ticks(t.ok_or_else(|| err("position out of range"))?)
+			};
+
+			let (variant, kind) = match (f[0].len(), f[0].get(..2), f[0].get(2..)) {
+				(5, Some(prefix), Some(kind)) => (Variant::from_prefix(prefix), kind),
+				_ => (None, f[0]),
+			};
+
+			let record = match (variant, kind) {
+				(_, "RESOLUTION") => {
+					resolution = int(1)?;
+					if resolution == 0 {
+						return Err(err("resolution must not be zero"));
+					}
+					continue;
+				}
+				(None, "BPM") => Record::Bpm {
+					at: at()?,
+					bpm: f.get(3).and_then(|s| s.parse().ok()).ok_or_else(|| err("invalid BPM"))?,
+				},
+				(None, "MET") => Record::Met { at: at()?, num: int(3)?, den: int(4)? },
+				(Some(variant), "TAP") => Record::Tap { at: at()?, key: key(3)?, variant },
+				(Some(variant), "STR") => Record::Star { at: at()?, key: key(3)?, variant },
+				(Some(variant), "HLD") => {
+					Record::Hold { at: at()?, key: key(3)?, len: ticks(int(4)?)?, variant }
+				}
+				(_, "TTP") => Record::Touch {
+					at: at()?,
+					sensor: sensor(f.get(3).unwrap_or(&""), f.get(4).unwrap_or(&""))
+						.ok_or_else(|| err("invalid sensor"))?,
+					firework: int(5)? != 0,
+				},
+				(_, "THO") => Record::TouchHold {
+					at: at()?,
+					sensor: sensor(f.get(3).unwrap_or(&""), f.get(5).unwrap_or(&""))
+						.ok_or_else(|| err("invalid sensor"))?,
+					len: ticks(int(4)?)?,
+					firework: int(6)? != 0,
+				},
+				(Some(variant), code) if code.starts_with('S') => Record::Slide {
+					at: at()?,
+					key: key(3)?,
+					code: code.to_string(),
+					wait: ticks(int(4)?)?,
+					len: ticks(int(5)?)?,
+					end: key(6)?,
+					variant,
+				},
+				_ => continue,
+			};
+			ma2.records.push(record);
+		}
+
+		Ok(ma2)
+	}
+}
+
+impl Display for Ma2 {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let bpms: Vec<f64> = self
+			.records
+			.iter()
+			.filter_map(|r| match r {
+				Record::Bpm { bpm, .. } => Some(*bpm),
+				_ => None,
+			})
+			.collect();
+		let first = bpms.first().copied().unwrap_or(120.);
+		let min = bpms.iter().copied().fold(first, f64::min);
+		let max = bpms.iter().copied().fold(first, f64::max);
+
+		writeln!(f, "VERSION\t0.00.00\t1.04.00")?;
+		writeln!(f, "FES_MODE\t0")?;
+		writeln!(f, "BPM_DEF\t{:.3}\t{:.3}\t{:.3}\t{:.3}", first, min, max, max)?;
+		writeln!(f, "MET_DEF\t4\t4")?;
+		writeln!(f, "RESOLUTION\t{}", RESOLUTION)?;
+		writeln!(f, "CLK_DEF\t{}", RESOLUTION)?;
+		writeln!(f, "COMPATIBLE_CODE\tMA2")?;
+		writeln!(f)?;
+
+		let pos = |at: &u32| format!("{}\t{}", at / RESOLUTION, at % RESOLUTION);
+		let idx = |k: &Key| *k as u8 - 1;
+		let (meta, notes): (Vec<_>, Vec<_>) =
+			self.records.iter().partition(|r| matches!(r, Record::Bpm { .. } | Record::Met { .. }));
+
+		for r in meta {
+			match r {
+				Record::Bpm { at, bpm } => writeln!(f, "BPM\t{}\t{:.3}", pos(at), bpm)?,
+				Record::Met { at, num, den } => writeln!(f, "MET\t{}\t{}\t{}", pos(at), num, den)?,
+				_ => unreachable!(),
+			}
+		}
+		writeln!(f)?;
+
+		for r in notes {
+			match r {
+				Record::Tap { at, key, variant } => {
+					writeln!(f, "{}TAP\t{}\t{}", variant.prefix(), pos(at), idx(key))?
+				}
+				Record::Star { at, key, variant } => {
+					writeln!(f, "{}STR\t{}\t{}", variant.prefix(), pos(at), idx(key))?
+				}
+				Record::Hold { at, key, len, variant } => {
+					writeln!(f, "{}HLD\t{}\t{}\t{}", variant.prefix(), pos(at), idx(key), len)?
+				}
+				Record::Slide { at, key, code, wait, len, end, variant } => writeln!(
+					f,
+					"{}{}\t{}\t{}\t{}\t{}\t{}",
+					variant.prefix(),
+					code,
+					pos(at),
+					idx(key),
+					wait,
+					len,
+					idx(end)
+				)?,
+				Record::Touch { at, sensor, firework } => writeln!(
+					f,
+					"NMTTP\t{}\t{}\t{}\t{}\tM1",
+					pos(at),
+					sensor.index.map_or(0, |k| idx(&k)),
+					sensor.group,
+					*firework as u8
+				)?,
+				Record::TouchHold { at, sensor, len, firework } => writeln!(
+					f,
+					"NMTHO\t{}\t{}\t{}\t{}\t{}\tM1",
+					pos(at),
+					sensor.index.map_or(0, |k| idx(&k)),
+					len,
+					sensor.group,
+					*firework as u8
+				)?,
+				_ => unreachable!(),
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Shape code of a slide segment.
+pub fn shape_code(shape: &Shape, from: Key, to: Key) -> &'static str {
+	let cw = match shape {
+		Shape::ArcRight => from.is_top(),
+		Shape::ArcLeft => !from.is_top(),
+		_ => from.offset(to) < 4,
+	};
+	match shape {
+		Shape::Line => "SI_",
+		Shape::ArcLeft | Shape::ArcRight | Shape::Arc if cw => "SCR",
+		Shape::ArcLeft | Shape::ArcRight | Shape::Arc => "SCL",
+		Shape::P => "SUL",
+		Shape::Q => "SUR",
+		Shape::S => "SSL",
+		Shape::Z => "SSR",
+		Shape::V => "SV_",
+		Shape::PP => "SXL",
+		Shape::QQ => "SXR",
+		Shape::Angle(mid) if from.offset(*mid) == 6 => "SLL",
+		Shape::Angle(_) => "SLR",
+		Shape::Fan => "SF_",
+	}
+}
+
+/// Slide shape of a shape code, or `None` for an unknown code.
+pub fn code_shape(code: &str, from: Key) -> Option<Shape> {
+	let arc = |cw: bool| if cw == from.is_top() { Shape::ArcRight } else { Shape::ArcLeft };
+	Some(match code {
+		"SI_" => Shape::Line,
+		"SCR" => arc(true),
+		"SCL" => arc(false),
+		"SUL" => Shape::P,
+		"SUR" => Shape::Q,
+		"SSL" => Shape::S,
+		"SSR" => Shape::Z,
+		"SV_" => Shape::V,
+		"SXL" => Shape::PP,
+		"SXR" => Shape::QQ,
+		"SLL" => Shape::Angle(from.rotate(-2)),
+		"SLR" => Shape::Angle(from.rotate(2)),
+		"SF_" => Shape::Fan,
+		_ => return None,
+	})
+}
+
+impl Ma2 {
+	fn tempo(&self) -> TempoMap {
+		let mut bpms: Vec<(u32, f64)> = self
+			.records
+			.iter()
+			.filter_map(|r| match r {
+				Record::Bpm { at, bpm } => Some((*at, *bpm)),
+				_ => None,
+			})
+			.collect();
+		bpms.sort_by_key(|(at, _)| *at);
+
+		let mut tempo = TempoMap::new();
+		let mut time = 0.;
+		let mut last = None;
+		for (at, bpm) in bpms {
+			if let Some((prev, prev_bpm)) = last {
+				time += (at - prev) as f64 / RESOLUTION as f64 * 240. / prev_bpm;
+			}
+			tempo.push(time, bpm);
+			last = Some((at, bpm));
+		}
+		tempo
+	}
+
+	/// Converts to simai items, with the smallest divisor each measure needs. Lengths are
+	/// relative where the BPM does not change under them. Fails on notes after
+	/// [`MAX_MEASURES`](crate::timeline::MAX_MEASURES).
+	pub fn to_items(&self) -> Result<Vec<Item>, QuantizeError> {
+		let tempo = self.tempo();
+		let measure = |t: u32| t as f64 / RESOLUTION as f64;
+		// rounded to the microsecond, well below a tick, to keep the written lengths short
+		let secs = |a: u32, b: u32| {
+			((tempo.time_at(measure(b)) - tempo.time_at(measure(a))) * 1e6).round() / 1e6
+		};
+		let steady = |a: u32, b: u32| {
+			!self.records.iter().any(|r| matches!(r, Record::Bpm { at, .. } if a < *at && *at < b))
+		};
+		// relative lengths follow the BPM at `note`, the position of the note they belong to
+		let len = |note: u32, at: u32, len: u32| match len {
+			0 => Len::Zero,
+			_ if steady(note, at + len) => Len::Rel(Frac::new(len, RESOLUTION)),
+			_ => Len::Abs(secs(at, at + len)),
+		};
+
+		// notes by position, with slides gathered under their star
+		let mut notes: Vec<(u32, Item)> = Vec::new();
+		let mut slides: Vec<&Record> = Vec::new();
+		for r in &self.records {
+			match r {
+				Record::Tap { at, key, variant } => notes.push((
+					*at,
+					Item::Tap(Tap { key: *key, style: TapStyle::from_bits_truncate(variant.style()) }),
+				)),
+				Record::Hold { at, key, len: l, variant } => notes.push((
+					*at,
+					Item::Hold(Hold {
+						key: *key,
+						len: len(*at, *at, *l),
+						style: HoldStyle::from_bits_truncate(variant.style()),
+					}),
+				)),
+				Record::Touch { at, sensor, firework } => notes.push((
+					*at,
+					Item::TouchTap(TouchTap {
+						sensor: *sensor,
+						style: TouchStyle::from_bits_truncate(if *firework { FIREWORK } else { NONE }),
+					}),
+				)),
+				Record::TouchHold { at, sensor, len: l, firework } => notes.push((
+					*at,
+					Item::TouchHold(TouchHold {
+						sensor: *sensor,
+						len: len(*at, *at, *l),
+						style: TouchStyle::from_bits_truncate(if *firework { FIREWORK } else { NONE }),
+					}),
+				)),
+				Record::Star { at, key, variant } => notes.push((
+					*at,
+					Item::Slide(Slide {
+						key: *key,
+						star_style: StarStyle::from_bits_truncate(variant.style()),
+						tracks: vec![],
+					}),
+				)),
+				Record::Slide { .. } => slides.push(r),
+				Record::Bpm { .. } | Record::Met { .. } => {}
+			}
+		}
+
+		// (end position, end key, note index, track index) of tracks that may be continued
+		let mut open: Vec<(u32, Key, usize, usize)> = Vec::new();
+		slides.sort_by_key(|r| (r.at(), matches!(r, Record::Slide { variant: Variant::Connect, .. })));
+		for r in slides {
+			let Record::Slide { at, key, code, wait, len: l, end, variant } = r else { unreachable!() };
+			let Some(shape) = code_shape(code, *key) else { continue };
+
+			if *variant == Variant::Connect
+				&& let Some(i) = open.iter().position(|(e, k, ..)| *e == *at && k == key)
+			{
+				let (_, _, n, t) = open.remove(i);
+				let seg_len = len(notes[n].0, *at, *l);
+				let Item::Slide(slide) = &mut notes[n].1 else { unreachable!() };
+				let track = &mut slide.tracks[t];
+				*track = match track.clone() {
+					SlideTrack::Amortized { path, wait, style, len } => {
+						let (s, k) = path[0].clone();
+						SlideTrack::Piecewise { path: vec![(s, k, len), (shape, *end, seg_len)], wait, style }
+					}
+					SlideTrack::Piecewise { mut path, wait, style } => {
+						path.push((shape, *end, seg_len));
+						SlideTrack::Piecewise { path, wait, style }
+					}
+				};
+				open.push((*at + *l, *end, n, t));
+				continue;
+			}
+
+			let wait_len = if steady(*at, *at + *wait + *l) && *wait == RESOLUTION / 4 {
+				Wait::Rel
+			} else {
+				Wait::Abs(secs(*at, *at + *wait))
+			};
+			let track = SlideTrack::Amortized {
+				path: vec![(shape, *end)],
+				wait: wait_len,
+				style: SlideStyle::from_bits_truncate(variant.style() & BREAK),
+				len: len(*at, *at + *wait, *l),
+			};
+
+			let n = notes
+				.iter()
+				.position(|(a, item)| matches!(item, Item::Slide(s) if *a == *at && s.key == *key));
+			let n = n.unwrap_or_else(|| {
+				notes.push((
+					*at,
+					Item::Slide(Slide {
+						key: *key,
+						star_style: StarStyle::from_bits_truncate(REMOVE),
+						tracks: vec![],
+					}),
+				));
+				notes.len() - 1
+			});
+			let Item::Slide(slide) = &mut notes[n].1 else { unreachable!() };
+			slide.tracks.push(track);
+			open.push((*at + *wait + *l, *end, n, slide.tracks.len() - 1));
+		}
+
+		// stars without slides are star shaped taps
+		for (_, item) in notes.iter_mut() {
+			if let Item::Slide(slide) = item
+				&& slide.tracks.is_empty()
+			{
+				let style = (slide.star_style.bits() & (BREAK | EX)) | NAKED_STAR;
+				*item = Item::Tap(Tap { key: slide.key, style: TapStyle::from_bits_truncate(style) });
+			}
+		}
+		notes.sort_by_key(|(at, _)| *at);
+
+		// every tick is a multiple of 1/384, so the quantizer only has to find the divisors
+		let notes: Vec<(f64, Item)> =
+			notes.into_iter().map(|(at, item)| (tempo.time_at(measure(at)), item)).collect();
+		Ok(Quantizer { tolerance: 1e-6, ..Default::default() }.quantize(&notes, &tempo)?.items)
+	}
+
+	pub fn to_chart(&self) -> Result<Chart, QuantizeError> {
+		Ok(crate::write::chart(&self.to_items()?).parse().unwrap())
+	}
+
+	/// Converts a resolved chart, rounding every time to the nearest tick.
+	pub fn from_timeline(timeline: &Timeline) -> Self {
+		let tempo = &timeline.tempo;
+		let tick = |time: f64| (tempo.measure_at(time) * RESOLUTION as f64).round().max(0.) as u32;
+
+		let mut records = vec![];
+		for c in tempo.changes() {
+			records.push(Record::Bpm { at: (c.measure * RESOLUTION as f64).round() as u32, bpm: c.bpm });
+		}
+		records.push(Record::Met { at: 0, num: 4, den: 4 });
+
+		for note in &timeline.notes {
+			let at = tick(note.time);
+			let ticks = |len: &Len| tick(note.time + len.to_abs(note.bpm).unwrap_or(0.)) - at;
+			match &note.item {
+				Item::Tap(tap) => {
+					let variant = Variant::from_style(tap.style.bits());
+					records.push(match tap.style.contains(TapStyle::from_bits_truncate(NAKED_STAR)) {
+						true => Record::Star { at, key: tap.key, variant },
+						false => Record::Tap { at, key: tap.key, variant },
+					});
+				}
+				Item::Hold(hold) => records.push(Record::Hold {
+					at,
+					key: hold.key,
+					len: ticks(&hold.len),
+					variant: Variant::from_style(hold.style.bits()),
+				}),
+				Item::TouchTap(touch) => records.push(Record::Touch {
+					at,
+					sensor: touch.sensor,
+					firework: touch.style.bits() & FIREWORK != 0,
+				}),
+				Item::TouchHold(touch) => records.push(Record::TouchHold {
+					at,
+					sensor: touch.sensor,
+					len: ticks(&touch.len),
+					firework: touch.style.bits() & FIREWORK != 0,
+				}),
+				Item::Slide(slide) => {
+					if slide.star_style.bits() & REMOVE == 0 {
+						let variant = Variant::from_style(slide.star_style.bits());
+						records.push(Record::Star { at, key: slide.key, variant });
+					}
+					for track in &slide.tracks {
+						let mut time = note.time + track.wait_secs(note.bpm);
+						let mut from = slide.key;
+						let mut start = at;
+						let segs = track.path().into_iter().zip(track.segment_secs(note.bpm));
+						for (i, ((shape, to), secs)) in segs.enumerate() {
+							let (begin, end) = (tick(time), tick(time + secs));
+							records.push(Record::Slide {
+								at: start,
+								key: from,
+								code: shape_code(shape, from, to).to_string(),
+								wait: begin - start,
+								len: end - begin,
+								end: to,
+								variant: match i {
+									0 => Variant::from_style(track.style().bits()),
+									_ => Variant::Connect,
+								},
+							});
+							time += secs;
+							from = to;
+							start = end;
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+
+		Ma2 { records }
+	}
+
+	pub fn from_chart(chart: &Chart) -> Self {
+		Ma2::from_timeline(&Timeline::from_chart(chart))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_shape_codes() {
+		let shapes = [
+			(Shape::Line, Key::K5, "SI_"),
+			(Shape::ArcRight, Key::K3, "SCR"),
+			(Shape::ArcLeft, Key::K3, "SCL"),
+			(Shape::P, Key::K4, "SUL"),
+			(Shape::Q, Key::K4, "SUR"),
+			(Shape::S, Key::K5, "SSL"),
+			(Shape::Z, Key::K5, "SSR"),
+			(Shape::V, Key::K3, "SV_"),
+			(Shape::PP, Key::K4, "SXL"),
+			(Shape::QQ, Key::K4, "SXR"),
+			(Shape::Angle(Key::K7), Key::K4, "SLL"),
+			(Shape::Angle(Key::K3), Key::K6, "SLR"),
+			(Shape::Fan, Key::K5, "SF_"),
+		];
+		for from in [Key::K1, Key::K6] {
+			for (shape, to, code) in &shapes {
+				let (shape, to) = match shape {
+					Shape::Angle(mid) => {
+						(Shape::Angle(mid.rotate(from as i32 - 1)), to.rotate(from as i32 - 1))
+					}
+					_ => (shape.clone(), to.rotate(from as i32 - 1)),
+				};
+				let code = if from.is_top() || !code.starts_with("SC") {
+					code.to_string()
+				} else {
+					code.replace('R', "_").replace('L', "R").replace('_', "L")
+				};
+				assert_eq!(shape_code(&shape, from, to), code, "{shape} from {from:?}");
+				assert_eq!(code_shape(&code, from), Some(shape));
+			}
+		}
+	}
+
+	#[test]
+	fn test_malformed_records() {
+		let line = |s: &str| s.parse::<Ma2>().map(|m| m.records.len()).map_err(|e| e.line);
+		assert_eq!(line("A\t1\t2\nAÄTA\t0\t0\t0\n"), Ok(0));
+		assert_eq!(line("NMTAP\t4294967295\t0\t0\n"), Err(1));
+		assert_eq!(line("RESOLUTION\t1\nNMHLD\t0\t0\t0\t4294967295\n"), Err(2));
+		assert_eq!(line("RESOLUTION\t0\n"), Err(1));
+	}
+}
@@ -0,0 +1,183 @@
+use serde::{Serialize, Serializer};
+
+use crate::def::*;
+use crate::parse::{Chart, line_col};
+use crate::timeline::{TimedNote, Timeline};
+
+/// Note type, serialized as its index like Majdata does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteType {
+	Tap,
+	Slide,
+	Hold,
+	Touch,
+	TouchHold,
+}
+
+impl Serialize for NoteType {
+	fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+		serializer.serialize_u8(*self as u8)
+	}
+}
+
+/// A single note. Slides with several tracks are split into one note per track, the ones after
+/// the first marked as having no head.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MajdataNote {
+	pub note_type: NoteType,
+	pub is_break: bool,
+	pub is_ex: bool,
+	pub is_hanabi: bool,
+	pub is_slide_break: bool,
+	pub is_slide_no_head: bool,
+	pub is_force_star: bool,
+	pub note_content: String,
+	/// Key of the note, or index of the touch sensor with the center as 8.
+	pub start_position: u8,
+	/// Sensor group of a touch note, a space for other notes.
+	pub touch_area: char,
+	pub hold_time: f64,
+	pub slide_start_time: f64,
+	pub slide_time: f64,
+}
+
+/// Notes hit at the same time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingPoint {
+	pub time: f64,
+	/// Column of the first note in the chart text.
+	pub raw_text_position_x: usize,
+	/// Line of the first note in the chart text.
+	pub raw_text_position_y: usize,
+	pub notes_content: String,
+	pub current_bpm: f64,
+	pub note_list: Vec<MajdataNote>,
+}
+
+/// Notes in the layout Majdata viewers load. Times are in seconds since the start of the
+/// chart, `&first` is left to the viewer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Majdata {
+	pub timing_list: Vec<TimingPoint>,
+}
+
+impl MajdataNote {
+	fn new(note_type: NoteType, start_position: u8, note_content: String) -> Self {
+		MajdataNote {
+			note_type,
+			is_break: false,
+			is_ex: false,
+			is_hanabi: false,
+			is_slide_break: false,
+			is_slide_no_head: false,
+			is_force_star: false,
+			note_content,
+			start_position,
+			touch_area: ' ',
+			hold_time: 0.,
+			slide_start_time: 0.,
+			slide_time: 0.,
+		}
+	}
+
+	fn from_timed(note: &TimedNote) -> Vec<Self> {
+		let len = |len: &Len| len.to_abs(note.bpm).unwrap_or(0.);
+		let has = |style: S, bit: S| style & bit != 0;
+
+		match &note.item {
+			Item::Tap(tap) => {
+				let s = tap.style.bits();
+				vec![MajdataNote {
+					is_break: has(s, BREAK),
+					is_ex: has(s, EX),
+					is_force_star: has(s, NAKED_STAR),
+					..MajdataNote::new(NoteType::Tap, tap.key as u8, tap.to_string())
+				}]
+			}
+			Item::Hold(hold) => {
+				let s = hold.style.bits();
+				vec![MajdataNote {
+					is_break: has(s, BREAK),
+					is_ex: has(s, EX),
+					hold_time: len(&hold.len),
+					..MajdataNote::new(NoteType::Hold, hold.key as u8, hold.to_string())
+				}]
+			}
+			Item::TouchTap(touch) => vec![MajdataNote {
+				is_hanabi: has(touch.style.bits(), FIREWORK),
+				touch_area: touch.sensor.group.into(),
+				..MajdataNote::new(NoteType::Touch, touch_position(&touch.sensor), touch.to_string())
+			}],
+			Item::TouchHold(touch) => vec![MajdataNote {
+				is_hanabi: has(touch.style.bits(), FIREWORK),
+				touch_area: touch.sensor.group.into(),
+				hold_time: len(&touch.len),
+				..MajdataNote::new(NoteType::TouchHold, touch_position(&touch.sensor), touch.to_string())
+			}],
+			Item::Slide(slide) => {
+				let s = slide.star_style.bits();
+				let headless = has(s, REMOVE) || has(s, SUDDEN);
+				slide
+					.tracks
+					.iter()
+					.enumerate()
+					.map(|(i, track)| {
+						let content = format!("{}{}{}", slide.key as u8, from_style(s), track);
+						let wait = track.wait_secs(note.bpm);
+						MajdataNote {
+							is_break: has(s, BREAK),
+							is_ex: has(s, EX),
+							is_slide_break: has(track.style().bits(), BREAK),
+							is_slide_no_head: headless || i > 0,
+							slide_start_time: note.time + wait,
+							slide_time: track.segment_secs(note.bpm).iter().sum(),
+							..MajdataNote::new(NoteType::Slide, slide.key as u8, content)
+						}
+					})
+					.collect()
+			}
+			_ => vec![],
+		}
+	}
+}
+
+fn touch_position(sensor: &Sensor) -> u8 {
+	sensor.index.map_or(8, |k| k as u8)
+}
+
+impl Majdata {
+	/// Groups the notes of `timeline` by time. `raw` is the text the spans point into, used for
+	/// the text positions.
+	pub fn from_timeline(timeline: &Timeline, raw: &str) -> Self {
+		let mut timing_list: Vec<TimingPoint> = Vec::new();
+		for note in &timeline.notes {
+			let notes = MajdataNote::from_timed(note);
+			match timing_list.last_mut() {
+				Some(point) if point.time == note.time => {
+					point.notes_content.push('/');
+					point.notes_content.push_str(&note.item.to_string());
+					point.note_list.extend(notes);
+				}
+				_ => {
+					let (y, x) = line_col(raw, note.span.start);
+					timing_list.push(TimingPoint {
+						time: note.time,
+						raw_text_position_x: x,
+						raw_text_position_y: y,
+						notes_content: note.item.to_string(),
+						current_bpm: note.bpm,
+						note_list: notes,
+					});
+				}
+			}
+		}
+		Majdata { timing_list }
+	}
+
+	pub fn from_chart(chart: &Chart) -> Self {
+		Majdata::from_timeline(&Timeline::from_chart(chart), &chart.raw)
+	}
+}
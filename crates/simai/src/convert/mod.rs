@@ -0,0 +1,7 @@
+mod ma2;
+mod majdata;
+mod osu;
+
+pub use ma2::*;
+pub use majdata::*;
+pub use osu::*;
@@ -0,0 +1,206 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::convert::RESOLUTION;
+use crate::def::*;
+use crate::parse::Chart;
+use crate::timeline::{QuantizeError, Quantized, Quantizer, TempoMap};
+
+/// A red (uninherited) timing point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OsuTimingPoint {
+	/// Milliseconds since the start of the audio.
+	pub time: f64,
+	/// Milliseconds per beat.
+	pub beat_len: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitObject {
+	pub column: usize,
+	/// Milliseconds since the start of the audio.
+	pub time: f64,
+	/// Release time of a hold.
+	pub end: Option<f64>,
+}
+
+/// The parts of an osu!mania beatmap needed for a draft chart.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Osu {
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	/// Key count, from `CircleSize`.
+	pub columns: usize,
+	pub timing_points: Vec<OsuTimingPoint>,
+	pub hit_objects: Vec<HitObject>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsuError {
+	/// 1-based line number.
+	pub line: usize,
+	pub message: String,
+}
+
+impl Display for OsuError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for OsuError {}
+
+impl FromStr for Osu {
+	type Err = OsuError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut osu = Osu::default();
+		let mut section = "";
+
+		for (i, line) in s.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with("//") {
+				continue;
+			}
+			if line.starts_with('[') && line.ends_with(']') {
+				section = &line[1..line.len() - 1];
+				continue;
+			}
+
+			let err = |message: &str| OsuError { line: i + 1, message: message.to_string() };
+			let f: Vec<&str> = line.split(',').map(str::trim).collect();
+			let num = |j: usize| -> Result<f64, OsuError> {
+				f.get(j).and_then(|s| s.parse().ok()).ok_or_else(|| err("expected a number"))
+			};
+
+			match section {
+				"Metadata" | "Difficulty" | "General" => {
+					let Some((k, v)) = line.split_once(':') else { continue };
+					let v = v.trim();
+					match k.trim() {
+						"Title" => osu.title = Some(v.to_string()),
+						"Artist" => osu.artist = Some(v.to_string()),
+						"CircleSize" => {
+							osu.columns = v.parse::<f64>().map_err(|_| err("invalid CircleSize"))? as usize
+						}
+						"Mode" if v != "3" => return Err(err("not an osu!mania beatmap")),
+						_ => {}
+					}
+				}
+				"TimingPoints" => {
+					let beat_len = num(1)?;
+					// inherited points only change the scroll speed
+					let uninherited = f.get(6).is_none_or(|s| *s == "1");
+					if uninherited && beat_len > 0. {
+						osu.timing_points.push(OsuTimingPoint { time: num(0)?, beat_len });
+					}
+				}
+				"HitObjects" => {
+					if osu.columns == 0 {
+						return Err(err("hit objects before CircleSize"));
+					}
+					let column = (num(0)? * osu.columns as f64 / 512.).floor() as usize;
+					let kind = num(3)? as u32;
+					let end = match kind & 128 {
+						0 => None,
+						_ => Some(
+							f.get(5)
+								.and_then(|s| s.split(':').next()?.parse().ok())
+								.ok_or_else(|| err("invalid hold end"))?,
+						),
+					};
+					osu.hit_objects.push(HitObject {
+						column: column.min(osu.columns - 1),
+						time: num(2)?,
+						end,
+					});
+				}
+				_ => {}
+			}
+		}
+
+		if osu.timing_points.is_empty() {
+			return Err(OsuError { line: 0, message: "no timing points".to_string() });
+		}
+		osu.timing_points.sort_by(|a, b| a.time.total_cmp(&b.time));
+		Ok(osu)
+	}
+}
+
+impl Osu {
+	/// Columns from left to right over consecutive keys, centered on the top of the ring.
+	/// `None` for more than 8 columns, which do not fit on the ring.
+	pub fn default_mapping(columns: usize) -> Option<Vec<Key>> {
+		if columns > 8 {
+			return None;
+		}
+		let start = Key::K1.rotate(-(columns as i32 / 2));
+		Some((0..columns).map(|i| start.rotate(i as i32)).collect())
+	}
+
+	/// Seconds from the start of the audio to the first timing point, where the chart starts.
+	pub fn first(&self) -> f64 {
+		self.timing_points[0].time / 1000.
+	}
+
+	fn tempo(&self) -> TempoMap {
+		let mut tempo = TempoMap::new();
+		for tp in &self.timing_points {
+			tempo.push(tp.time / 1000. - self.first(), 60000. / tp.beat_len);
+		}
+		tempo
+	}
+
+	/// Places the notes with `quantizer`, so off-grid notes are reported instead of being
+	/// silently rounded. Hold lengths are rounded to 1/384 of a measure.
+	pub fn quantize(
+		&self,
+		mapping: &[Key],
+		quantizer: &Quantizer,
+	) -> Result<Quantized, QuantizeError> {
+		let tempo = self.tempo();
+		let secs = |ms: f64| ms / 1000. - self.first();
+
+		let notes: Vec<(f64, Item)> = self
+			.hit_objects
+			.iter()
+			.filter_map(|obj| {
+				let key = *mapping.get(obj.column)?;
+				let item = match obj.end {
+					Some(end) => {
+						let (start, end) = (secs(obj.time), secs(end));
+						let steady = tempo.bpm_at(start) == tempo.bpm_at(end);
+						let ticks = (tempo.measure_at(end) - tempo.measure_at(start)) * RESOLUTION as f64;
+						let len = match ticks.round() as u32 {
+							0 => Len::Zero,
+							n if steady => Len::Rel(Frac::new(n, RESOLUTION)),
+							_ => Len::Abs(((end - start) * 1e6).round() / 1e6),
+						};
+						Item::Hold(Hold { key, len, style: HoldStyle::empty() })
+					}
+					None => Item::Tap(Tap { key, style: TapStyle::empty() }),
+				};
+				Some((secs(obj.time), item))
+			})
+			.collect();
+		quantizer.quantize(&notes, &tempo)
+	}
+
+	pub fn to_chart(&self, mapping: &[Key]) -> Result<Chart, QuantizeError> {
+		let items = self.quantize(mapping, &Quantizer::default())?.items;
+		Ok(crate::write::chart(&items).parse().unwrap())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_default_mapping() {
+		let keys = |n| Osu::default_mapping(n).unwrap().iter().map(|k| *k as u8).collect::<Vec<_>>();
+		assert_eq!(keys(4), [7, 8, 1, 2]);
+		assert_eq!(keys(7), [6, 7, 8, 1, 2, 3, 4]);
+		assert_eq!(keys(8), [5, 6, 7, 8, 1, 2, 3, 4]);
+		assert_eq!(Osu::default_mapping(9), None);
+	}
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 mod misc;
 mod note;
 mod style;
@@ -25,3 +27,23 @@ pub enum Item {
 
 	Error,
 }
+
+/// Writes a single item. Notes sharing a tick are not joined here, see [`crate::write::chart`].
+impl Display for Item {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Item::Bpm(Bpm(bpm)) => write!(f, "({})", bpm),
+			Item::Div(Div(div)) => write!(f, "{{{}}}", div),
+			Item::DivAbs(DivAbs(abs)) => write!(f, "{{#{}}}", abs),
+			Item::Tap(tap) => write!(f, "{}", tap),
+			Item::Hold(hold) => write!(f, "{}", hold),
+			Item::TouchTap(touch) => write!(f, "{}", touch),
+			Item::TouchHold(touch) => write!(f, "{}", touch),
+			Item::Slide(slide) => write!(f, "{}", slide),
+			Item::Tick(Tick(n)) => write!(f, "{}", ",".repeat(*n as usize)),
+			Item::PseudoTick(PseudoTick(n)) => write!(f, "{}", "`".repeat(*n as usize)),
+			Item::End => write!(f, "E"),
+			Item::Error => Ok(()),
+		}
+	}
+}
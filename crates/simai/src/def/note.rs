@@ -3,7 +3,7 @@ use std::fmt::Display;
 use fraction::{BigFraction, ToPrimitive};
 use serde::{Deserialize, Serialize};
 
-use crate::def::{HoldStyle, SlideStyle, StarStyle, TapStyle, TouchStyle};
+use crate::def::{HoldStyle, SlideStyle, StarStyle, TapStyle, TouchStyle, from_style};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[rustfmt::skip]
@@ -25,6 +25,27 @@ impl From<char> for Key {
 	}
 }
 
+impl Key {
+	pub const ALL: [Key; 8] =
+		[Key::K1, Key::K2, Key::K3, Key::K4, Key::K5, Key::K6, Key::K7, Key::K8];
+
+	/// The key `n` steps clockwise from this one (counter-clockwise if `n` is negative).
+	pub fn rotate(self, n: i32) -> Key {
+		Key::ALL[(self as i32 - 1 + n).rem_euclid(8) as usize]
+	}
+
+	/// Whether the key is in the top half, where `>` goes clockwise. From the bottom half it goes
+	/// counter-clockwise.
+	pub fn is_top(self) -> bool {
+		matches!(self, Key::K1 | Key::K2 | Key::K7 | Key::K8)
+	}
+
+	/// Clockwise distance from this key to `other`, in `0..8`.
+	pub fn offset(self, other: Key) -> u8 {
+		(other as i32 - self as i32).rem_euclid(8) as u8
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[rustfmt::skip]
 pub enum SensorGroup { A, B, C, D, E }
@@ -89,6 +110,32 @@ impl Len {
 			Len::Zero => 0.,
 		})
 	}
+
+	// the bracket contents, shared with slide waits
+	fn body(&self) -> String {
+		match self {
+			Len::Rel(frac) => ratio(frac),
+			Len::Bpm { bpm, frac } => format!("{}#{}", bpm, ratio(frac)),
+			Len::Abs(abs) => format!("#{}", abs),
+			Len::Zero => String::new(),
+		}
+	}
+}
+
+fn ratio(frac: &Frac) -> String {
+	match (frac.numer(), frac.denom()) {
+		(Some(n), Some(d)) => format!("{}:{}", d, n),
+		_ => "0:0".to_string(),
+	}
+}
+
+impl Display for Len {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Len::Zero => Ok(()),
+			len => write!(f, "[{}]", len.body()),
+		}
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -106,6 +153,27 @@ impl Wait {
 			Wait::Abs(abs) => *abs,
 		})
 	}
+
+	/// Whether the wait and the slide length can be written together as they are. Other
+	/// combinations have to be written with an absolute wait, see [`Wait::to_abs`].
+	pub fn spells(&self, len: &Len) -> bool {
+		match (self, len) {
+			(Wait::Rel, Len::Rel(_)) | (Wait::Bpm(_), Len::Abs(_)) => true,
+			(Wait::Bpm(a), Len::Bpm { bpm: b, .. }) => a == b,
+			(Wait::Abs(_), len) => *len != Len::Zero,
+			_ => false,
+		}
+	}
+
+	/// Writes the bracketed wait and length of a slide, assuming [`Wait::spells`] holds.
+	pub fn fmt_with(&self, len: &Len) -> String {
+		match (self, len) {
+			(Wait::Abs(t), Len::Abs(abs)) => format!("[{}##{}]", t, abs),
+			(Wait::Abs(t), len) => format!("[{}##{}]", t, len.body()),
+			(Wait::Bpm(bpm), Len::Abs(abs)) => format!("[{}#{}]", bpm, abs),
+			(_, len) => format!("[{}]", len.body()),
+		}
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -169,6 +237,24 @@ pub struct Sensor {
 	pub index: Option<Key>,
 }
 
+impl Sensor {
+	/// The center sensor. `C1` and `C2` are the same area and are usually written as this.
+	pub const C: Sensor = Sensor { group: SensorGroup::C, index: None };
+
+	pub fn new(group: SensorGroup, index: Key) -> Self {
+		Sensor { group, index: Some(index) }
+	}
+}
+
+impl Display for Sensor {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.index {
+			Some(k) => write!(f, "{}{}", self.group, k as u8),
+			None => write!(f, "{}", self.group),
+		}
+	}
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tap {
 	pub key: Key,
@@ -207,3 +293,87 @@ pub enum SlideTrack {
 	Amortized { path: Vec<(Shape, Key)>, wait: Wait, style: SlideStyle, len: Len },
 	Piecewise { path: Vec<(Shape, Key, Len)>, wait: Wait, style: SlideStyle },
 }
+
+impl SlideTrack {
+	/// The `(shape, key)` segments of the track, regardless of how their lengths are given.
+	pub fn path(&self) -> Vec<(&Shape, Key)> {
+		match self {
+			SlideTrack::Amortized { path, .. } => path.iter().map(|(s, k)| (s, *k)).collect(),
+			SlideTrack::Piecewise { path, .. } => path.iter().map(|(s, k, _)| (s, *k)).collect(),
+		}
+	}
+
+	pub fn wait(&self) -> &Wait {
+		match self {
+			SlideTrack::Amortized { wait, .. } | SlideTrack::Piecewise { wait, .. } => wait,
+		}
+	}
+
+	pub fn style(&self) -> SlideStyle {
+		match self {
+			SlideTrack::Amortized { style, .. } | SlideTrack::Piecewise { style, .. } => *style,
+		}
+	}
+}
+
+impl Display for Tap {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.key as u8, from_style(self.style.bits()))
+	}
+}
+
+impl Display for Hold {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}h{}", self.key as u8, from_style(self.style.bits()), self.len)
+	}
+}
+
+impl Display for TouchTap {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.sensor, from_style(self.style.bits()))
+	}
+}
+
+impl Display for TouchHold {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}h{}", self.sensor, from_style(self.style.bits()), self.len)
+	}
+}
+
+impl Display for SlideTrack {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let style = from_style(self.style().bits());
+		match self {
+			SlideTrack::Amortized { path, wait, len, .. } => {
+				for (shape, key) in path {
+					write!(f, "{}{}", shape, *key as u8)?;
+				}
+				write!(f, "{}{}", style, wait.fmt_with(len))
+			}
+			SlideTrack::Piecewise { path, wait, .. } => {
+				for (i, (shape, key, len)) in path.iter().enumerate() {
+					write!(f, "{}{}", shape, *key as u8)?;
+					if i == 0 {
+						write!(f, "{}", wait.fmt_with(len))?;
+					} else {
+						write!(f, "{}", len)?;
+					}
+				}
+				write!(f, "{}", style)
+			}
+		}
+	}
+}
+
+impl Display for Slide {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.key as u8, from_style(self.star_style.bits()))?;
+		for (i, track) in self.tracks.iter().enumerate() {
+			if i > 0 {
+				write!(f, "*")?;
+			}
+			write!(f, "{}", track)?;
+		}
+		Ok(())
+	}
+}
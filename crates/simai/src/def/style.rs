@@ -47,6 +47,19 @@ pub fn to_style(c: char) -> S {
 	}
 }
 
+pub fn from_style(s: S) -> String {
+	let chars = [
+		(BREAK, 'b'),
+		(EX, 'x'),
+		(NAKED_STAR, '$'),
+		(TAP_STAR, '@'),
+		(FIREWORK, 'f'),
+		(SUDDEN, '!'),
+		(REMOVE, '?'),
+	];
+	chars.iter().filter(|(bit, _)| s & bit != 0).map(|(_, c)| c).collect()
+}
+
 pub(crate) fn merge<T: Flags<Bits = S>>(v: &[S]) -> T {
 	let mut style = NONE;
 	for &s in v {
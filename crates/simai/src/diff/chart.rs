@@ -0,0 +1,346 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::def::*;
+use crate::timeline::{SIMULTANEOUS, TempoChange, TempoMap, TimedNote, Timeline};
+
+/// Where a change happened, in seconds and in measures and beats counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Position {
+	pub time: f64,
+	pub measure: u32,
+	pub beat: f64,
+}
+
+impl Position {
+	pub(crate) fn new(tempo: &TempoMap, time: f64) -> Self {
+		let m = tempo.measure_at(time) + 1e-9;
+		let beat = ((m.fract() * 4. + 1.) * 1000.).round() / 1000.;
+		Position { time, measure: m.floor() as u32 + 1, beat }
+	}
+}
+
+impl Display for Position {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "measure {} beat {}", self.measure, self.beat)
+	}
+}
+
+/// What differs between two notes matched to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aspect {
+	Kind,
+	Style,
+	Length,
+	SlidePath,
+	Time,
+}
+
+impl Display for Aspect {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Aspect::Kind => write!(f, "kind"),
+			Aspect::Style => write!(f, "style"),
+			Aspect::Length => write!(f, "length"),
+			Aspect::SlidePath => write!(f, "slide path"),
+			Aspect::Time => write!(f, "time"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+	Added {
+		at: Position,
+		note: String,
+	},
+	Removed {
+		at: Position,
+		note: String,
+	},
+	Modified {
+		at: Position,
+		old: String,
+		new: String,
+		aspects: Vec<Aspect>,
+	},
+	/// A BPM change added, removed or set to another value.
+	Bpm {
+		at: Position,
+		old: Option<f64>,
+		new: Option<f64>,
+	},
+}
+
+impl Change {
+	pub fn at(&self) -> Position {
+		match self {
+			Change::Added { at, .. }
+			| Change::Removed { at, .. }
+			| Change::Modified { at, .. }
+			| Change::Bpm { at, .. } => *at,
+		}
+	}
+}
+
+/// One change per line, e.g. `measure 12 beat 3: tap 1 -> break tap 1b (style)`.
+impl Display for Change {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}: ", self.at())?;
+		let bpm = |b: &Option<f64>| b.map_or("none".to_string(), |b| b.to_string());
+		match self {
+			Change::Added { note, .. } => write!(f, "added {}", note),
+			Change::Removed { note, .. } => write!(f, "removed {}", note),
+			Change::Modified { old, new, aspects, .. } => {
+				let aspects: Vec<String> = aspects.iter().map(|a| a.to_string()).collect();
+				write!(f, "{} -> {} ({})", old, new, aspects.join(", "))
+			}
+			Change::Bpm { old, new, .. } => write!(f, "bpm {} -> {}", bpm(old), bpm(new)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Diff {
+	pub changes: Vec<Change>,
+}
+
+impl Diff {
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+}
+
+impl Display for Diff {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for change in &self.changes {
+			writeln!(f, "{}", change)?;
+		}
+		Ok(())
+	}
+}
+
+/// The button or sensor a note is on. Notes are only matched within the same lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lane {
+	Key(Key),
+	Sensor(Sensor),
+}
+
+fn lane(item: &Item) -> Option<Lane> {
+	Some(match item {
+		Item::Tap(Tap { key, .. }) | Item::Hold(Hold { key, .. }) | Item::Slide(Slide { key, .. }) => {
+			Lane::Key(*key)
+		}
+		Item::TouchTap(TouchTap { sensor, .. }) | Item::TouchHold(TouchHold { sensor, .. }) => {
+			Lane::Sensor(sensor.normalize())
+		}
+		_ => return None,
+	})
+}
+
+/// Readable name of a note, e.g. `break tap 1b`.
+pub fn describe(item: &Item) -> String {
+	let (kind, style) = match item {
+		Item::Tap(tap) => ("tap", tap.style.bits()),
+		Item::Hold(hold) => ("hold", hold.style.bits()),
+		Item::TouchTap(touch) => ("touch", touch.style.bits()),
+		Item::TouchHold(touch) => ("touch hold", touch.style.bits()),
+		Item::Slide(slide) => ("slide", slide.star_style.bits()),
+		_ => return item.to_string(),
+	};
+	let mut words = vec![];
+	if style & BREAK != 0 {
+		words.push("break");
+	}
+	if style & EX != 0 {
+		words.push("ex");
+	}
+	if style & FIREWORK != 0 {
+		words.push("firework");
+	}
+	words.push(kind);
+	format!("{} {}", words.join(" "), item)
+}
+
+// `epsilon` is how far apart in seconds times and lengths may be and still count as unchanged
+fn aspects(old: &TimedNote, new: &TimedNote, epsilon: f64) -> Vec<Aspect> {
+	let mut v = vec![];
+	let len = |len: &Len, bpm: f64| len.to_abs(bpm).unwrap_or(0.);
+	let close = |a: f64, b: f64| (a - b).abs() < epsilon;
+
+	match (&old.item, &new.item) {
+		(Item::Tap(a), Item::Tap(b)) => {
+			if a.style != b.style {
+				v.push(Aspect::Style);
+			}
+		}
+		(Item::Hold(a), Item::Hold(b)) => {
+			if a.style != b.style {
+				v.push(Aspect::Style);
+			}
+			if !close(len(&a.len, old.bpm), len(&b.len, new.bpm)) {
+				v.push(Aspect::Length);
+			}
+		}
+		(Item::TouchTap(a), Item::TouchTap(b)) => {
+			if a.style != b.style {
+				v.push(Aspect::Style);
+			}
+		}
+		(Item::TouchHold(a), Item::TouchHold(b)) => {
+			if a.style != b.style {
+				v.push(Aspect::Style);
+			}
+			if !close(len(&a.len, old.bpm), len(&b.len, new.bpm)) {
+				v.push(Aspect::Length);
+			}
+		}
+		(Item::Slide(a), Item::Slide(b)) => {
+			let styles =
+				|s: &Slide| (s.star_style, s.tracks.iter().map(|t| t.style()).collect::<Vec<_>>());
+			if styles(a) != styles(b) {
+				v.push(Aspect::Style);
+			}
+			fn paths(s: &Slide) -> Vec<Vec<(&Shape, Key)>> {
+				s.tracks.iter().map(|t| t.path()).collect()
+			}
+			if paths(a) != paths(b) {
+				v.push(Aspect::SlidePath);
+			}
+			// when each track starts and how long it takes
+			let timing = |s: &Slide, bpm: f64| -> Vec<(f64, f64)> {
+				s.tracks.iter().map(|t| (t.wait_secs(bpm), t.segment_secs(bpm).iter().sum())).collect()
+			};
+			let (ta, tb) = (timing(a, old.bpm), timing(b, new.bpm));
+			if ta.len() != tb.len()
+				|| ta.iter().zip(&tb).any(|(x, y)| !close(x.0, y.0) || !close(x.1, y.1))
+			{
+				v.push(Aspect::Length);
+			}
+		}
+		_ => v.push(Aspect::Kind),
+	}
+	if !close(old.time, new.time) {
+		v.push(Aspect::Time);
+	}
+	v
+}
+
+/// Changes to the notes, unsorted.
+pub(crate) fn note_changes(
+	old: &Timeline,
+	new: &Timeline,
+	tolerance: f64,
+	epsilon: f64,
+) -> Vec<Change> {
+	let mut changes = vec![];
+	let mut matched = vec![false; new.notes.len()];
+
+	for a in &old.notes {
+		let Some(lane_a) = lane(&a.item) else { continue };
+		// the closest unmatched note on the lane, preferring one that did not change
+		let candidate = new
+			.notes
+			.iter()
+			.enumerate()
+			.filter(|(j, b)| {
+				!matched[*j] && lane(&b.item) == Some(lane_a) && (a.time - b.time).abs() <= tolerance
+			})
+			.min_by(|(_, x), (_, y)| {
+				let key = |b: &TimedNote| (b.item != a.item, (a.time - b.time).abs());
+				key(x).partial_cmp(&key(y)).unwrap()
+			});
+
+		match candidate {
+			Some((j, b)) => {
+				matched[j] = true;
+				let aspects = aspects(a, b, epsilon);
+				if !aspects.is_empty() {
+					changes.push(Change::Modified {
+						at: Position::new(&new.tempo, b.time),
+						old: describe(&a.item),
+						new: describe(&b.item),
+						aspects,
+					});
+				}
+			}
+			None => changes
+				.push(Change::Removed { at: Position::new(&old.tempo, a.time), note: describe(&a.item) }),
+		}
+	}
+
+	for (b, _) in new.notes.iter().zip(&matched).filter(|(b, m)| !**m && lane(&b.item).is_some()) {
+		changes.push(Change::Added { at: Position::new(&new.tempo, b.time), note: describe(&b.item) });
+	}
+	changes
+}
+
+/// Compares two charts note by note. Notes on the same key or sensor less than `tolerance`
+/// seconds apart are taken to be the same note; a note is reported as modified when anything
+/// but its text position changed.
+pub fn diff(old: &Timeline, new: &Timeline, tolerance: f64) -> Diff {
+	let mut changes = note_changes(old, new, tolerance, SIMULTANEOUS);
+
+	let (old_bpms, new_bpms) = (old.tempo.changes(), new.tempo.changes());
+	let find = |changes: &[TempoChange], time: f64| {
+		changes.iter().find(|c| (c.time - time).abs() <= tolerance).map(|c| c.bpm)
+	};
+	for c in old_bpms {
+		let other = find(new_bpms, c.time);
+		if other != Some(c.bpm) {
+			changes.push(Change::Bpm {
+				at: Position::new(&old.tempo, c.time),
+				old: Some(c.bpm),
+				new: other,
+			});
+		}
+	}
+	for c in new_bpms {
+		if find(old_bpms, c.time).is_none() {
+			changes.push(Change::Bpm {
+				at: Position::new(&new.tempo, c.time),
+				old: None,
+				new: Some(c.bpm),
+			});
+		}
+	}
+
+	changes.sort_by(|a, b| a.at().time.total_cmp(&b.at().time));
+	Diff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn diff_str(old: &str, new: &str) -> String {
+		let timeline = |s: &str| Timeline::from_chart(&s.parse::<Chart>().unwrap());
+		diff(&timeline(old), &timeline(new), 0.001).to_string()
+	}
+
+	#[test]
+	fn test_diff() {
+		assert_eq!(diff_str("(120){4}1,2,3h[4:1],E", "(120){4}1,2,3h[4:1],E"), "");
+		assert_eq!(
+			diff_str("(120){4}1,2,3h[4:1],4,E", "(120){4}1,2b,3h[2:1],,5,E"),
+			"measure 1 beat 2: tap 2 -> break tap 2b (style)\n\
+			 measure 1 beat 3: hold 3h[4:1] -> hold 3h[2:1] (length)\n\
+			 measure 1 beat 4: removed tap 4\n\
+			 measure 2 beat 1: added tap 5\n"
+		);
+		assert_eq!(
+			diff_str("(120){4}1-5[4:1],E", "(150){4}1,,,,(120)1>5[4:1],E"),
+			"measure 1 beat 1: slide 1-5[4:1] -> tap 1 (kind)\n\
+			 measure 1 beat 1: bpm 120 -> 150\n\
+			 measure 2 beat 1: added slide 1>5[4:1]\n\
+			 measure 2 beat 1: bpm none -> 120\n"
+		);
+		// C1 and C2 are other names for C
+		assert_eq!(diff_str("(120){4}C,C2h[4:1],E", "(120){4}C1,Ch[4:1],E"), "");
+	}
+}
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::parse::Chart;
+use crate::timeline::Timeline;
+
+use super::{Change, Position, note_changes};
+
+/// Whether two charts play the same, however they are written.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Equivalence {
+	Equivalent,
+	/// The earliest difference between the two.
+	Diverges {
+		change: Change,
+	},
+}
+
+impl Equivalence {
+	/// Compares the resolved timelines of two charts. Notes must be on the same key or sensor and
+	/// no more than `tolerance` seconds apart, with the same style and slide paths and lengths
+	/// within `tolerance`, and the BPM must be the same throughout. Redundant BPM marks, divisors
+	/// and how lengths are spelled make no difference.
+	pub fn check(a: &Chart, b: &Chart, tolerance: f64) -> Self {
+		let (a, b) = (Timeline::from_chart(a), Timeline::from_chart(b));
+		let mut changes = note_changes(&a, &b, tolerance, tolerance + 1e-9);
+
+		let mut times: Vec<f64> =
+			a.tempo.changes().iter().chain(b.tempo.changes()).map(|c| c.time).collect();
+		times.sort_by(f64::total_cmp);
+		let bpm_change = times.into_iter().find_map(|time| {
+			let (old, new) = (a.tempo.bpm_at(time), b.tempo.bpm_at(time));
+			(old != new).then(|| Change::Bpm { at: Position::new(&a.tempo, time), old, new })
+		});
+		changes.extend(bpm_change);
+
+		match changes.into_iter().min_by(|x, y| x.at().time.total_cmp(&y.at().time)) {
+			Some(change) => Equivalence::Diverges { change },
+			None => Equivalence::Equivalent,
+		}
+	}
+
+	pub fn is_equivalent(&self) -> bool {
+		*self == Equivalence::Equivalent
+	}
+}
+
+impl Display for Equivalence {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Equivalence::Equivalent => write!(f, "equivalent"),
+			Equivalence::Diverges { change } => write!(f, "first divergence at {}", change),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn check(a: &str, b: &str) -> String {
+		Equivalence::check(&a.parse().unwrap(), &b.parse().unwrap(), 0.001).to_string()
+	}
+
+	#[test]
+	fn test_equivalent() {
+		assert_eq!(check("(120){4}1,1,E", "(120){8}1,,1,,E"), "equivalent");
+		assert_eq!(check("(120){4}1h[4:1],E", "(120)1h[8:2],E"), "equivalent");
+		assert_eq!(check("(120){4}1h[4:1],E", "(120){4}1h[#0.5],(120)E"), "equivalent");
+		assert_eq!(check("(120){4}1-5[4:1],E", "(120){4}1-5[0.5##0.5],E"), "equivalent");
+		assert_eq!(check("(120){4}1/2,E", "(120){4}2/1,E"), "equivalent");
+	}
+
+	#[test]
+	fn test_diverges() {
+		assert_eq!(
+			check("(120){4}1,2,3,E", "(120){4}1,2,4,E"),
+			"first divergence at measure 1 beat 3: removed tap 3"
+		);
+		assert_eq!(
+			check("(120){4}1h[4:1],E", "(120){4}1h[2:1],E"),
+			"first divergence at measure 1 beat 1: hold 1h[4:1] -> hold 1h[2:1] (length)"
+		);
+		assert_eq!(
+			check("(120){4}1-5[4:1],E", "(120){4}1-5[0.25##0.75],E"),
+			"first divergence at measure 1 beat 1: slide 1-5[4:1] -> slide 1-5[0.25##0.75] (length)"
+		);
+		assert_eq!(
+			check("(120){4}1,,,,(150)1,E", "(120){4}1,,,,1,E"),
+			"first divergence at measure 2 beat 1: bpm 150 -> 120"
+		);
+	}
+}
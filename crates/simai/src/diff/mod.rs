@@ -0,0 +1,5 @@
+mod chart;
+mod equivalence;
+
+pub use chart::*;
+pub use equivalence::*;
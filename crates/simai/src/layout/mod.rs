@@ -0,0 +1,4 @@
+mod point;
+mod sensor;
+
+pub use point::*;
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::def::Key;
+
+/// A point on the playfield. The origin is the center of the screen, the ring the keys sit on
+/// has radius 1, and `y` grows downwards as in screen coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+	pub x: f64,
+	pub y: f64,
+}
+
+impl Point {
+	pub const ORIGIN: Point = Point { x: 0., y: 0. };
+
+	pub fn new(x: f64, y: f64) -> Self {
+		Point { x, y }
+	}
+
+	/// A point at `radius` from the center, `angle` degrees clockwise from the top.
+	pub fn polar(angle: f64, radius: f64) -> Self {
+		let rad = angle.to_radians();
+		Point { x: radius * rad.sin(), y: -radius * rad.cos() }
+	}
+
+	/// Angle in degrees clockwise from the top, in `0..360`.
+	pub fn angle(&self) -> f64 {
+		self.x.atan2(-self.y).to_degrees().rem_euclid(360.)
+	}
+
+	pub fn radius(&self) -> f64 {
+		self.x.hypot(self.y)
+	}
+
+	pub fn dist(&self, other: Point) -> f64 {
+		(self.x - other.x).hypot(self.y - other.y)
+	}
+
+	pub fn lerp(&self, other: Point, t: f64) -> Point {
+		Point { x: self.x + (other.x - self.x) * t, y: self.y + (other.y - self.y) * t }
+	}
+}
+
+impl Key {
+	/// Angle of the key in degrees clockwise from the top. Key 1 sits right of the top.
+	pub fn angle(self) -> f64 {
+		22.5 + 45. * (self as u8 - 1) as f64
+	}
+
+	/// Position of the key on the ring.
+	pub fn pos(self) -> Point {
+		Point::polar(self.angle(), 1.)
+	}
+}
@@ -0,0 +1,134 @@
+use crate::def::{Key, Sensor, SensorGroup};
+use crate::layout::Point;
+
+// Radii of the rings the touch panel is cut into, relative to the key ring.
+const R_C: f64 = 0.27;
+const R_B: f64 = 0.58;
+const R_E: f64 = 0.69;
+const R_D: f64 = 0.8;
+const R_EDGE: f64 = 1.;
+
+const EPS: f64 = 1e-9;
+
+impl Sensor {
+	/// All 33 distinct sensors, grouped by ring from the outside in: A, D, E, B, then C.
+	pub fn all() -> Vec<Sensor> {
+		let mut v = Vec::with_capacity(33);
+		for group in [SensorGroup::A, SensorGroup::D, SensorGroup::E, SensorGroup::B] {
+			v.extend(Key::ALL.iter().map(|&k| Sensor::new(group, k)));
+		}
+		v.push(Sensor::C);
+		v
+	}
+
+	/// Angle of the sensor in degrees clockwise from the top. A and B sensors line up with their
+	/// key, D and E sensors sit on the border between key `n - 1` and key `n`. The center sensor
+	/// is reported at 0.
+	pub fn angle(&self) -> f64 {
+		match (self.group, self.index) {
+			(SensorGroup::A | SensorGroup::B, Some(k)) => k.angle(),
+			(SensorGroup::D | SensorGroup::E, Some(k)) => k.angle() - 22.5,
+			_ => 0.,
+		}
+	}
+
+	/// Outline of the sensor, clockwise.
+	pub fn polygon(&self) -> Vec<Point> {
+		let p = Point::polar;
+		let t = self.angle();
+		let (lo, hi, d) = (t - 22.5, t + 22.5, 11.25);
+
+		match self.group {
+			SensorGroup::A => vec![
+				p(lo + d, R_E),
+				p(hi - d, R_E),
+				p(hi, R_D),
+				p(hi - d, R_EDGE),
+				p(t, R_EDGE),
+				p(lo + d, R_EDGE),
+				p(lo, R_D),
+			],
+			SensorGroup::B => {
+				vec![p(lo, R_C), p(hi, R_C), p(hi, R_B), p(hi - d, R_E), p(lo + d, R_E), p(lo, R_B)]
+			}
+			SensorGroup::D => vec![p(t, R_D), p(t + d, R_EDGE), p(t - d, R_EDGE)],
+			SensorGroup::E => vec![p(t, R_B), p(t + d, R_E), p(t, R_D), p(t - d, R_E)],
+			SensorGroup::C => (0..8).map(|i| p(45. * i as f64, R_C)).collect(),
+		}
+	}
+
+	/// Centroid of the sensor's outline.
+	pub fn center(&self) -> Point {
+		let poly = self.polygon();
+		let (mut area, mut x, mut y) = (0., 0., 0.);
+		for (a, b) in poly.iter().zip(poly.iter().cycle().skip(1)) {
+			let cross = a.x * b.y - b.x * a.y;
+			area += cross;
+			x += (a.x + b.x) * cross;
+			y += (a.y + b.y) * cross;
+		}
+		Point::new(x / (3. * area), y / (3. * area))
+	}
+
+	pub fn contains(&self, point: Point) -> bool {
+		let poly = self.polygon();
+		let mut inside = false;
+		for (a, b) in poly.iter().zip(poly.iter().cycle().skip(1)) {
+			if (a.y > point.y) != (b.y > point.y)
+				&& point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+			{
+				inside = !inside;
+			}
+		}
+		inside
+	}
+
+	/// The sensor under `point`, if any.
+	pub fn hit(point: Point) -> Option<Sensor> {
+		Sensor::all().into_iter().find(|s| s.contains(point))
+	}
+
+	/// Sensors sharing an edge with this one, in the order of [`Sensor::all`].
+	pub fn neighbors(&self) -> Vec<Sensor> {
+		let poly = self.polygon();
+		let shared = |other: &Sensor| {
+			other.polygon().iter().filter(|a| poly.iter().any(|b| a.dist(*b) < EPS)).count()
+		};
+		Sensor::all().into_iter().filter(|s| s != &self.normalize() && shared(s) >= 2).collect()
+	}
+
+	pub fn is_adjacent(&self, other: &Sensor) -> bool {
+		self.neighbors().contains(&other.normalize())
+	}
+
+	/// `C1` and `C2` are the same sensor as `C`.
+	pub(crate) fn normalize(&self) -> Sensor {
+		match self.group {
+			SensorGroup::C => Sensor::C,
+			_ => *self,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hit_center() {
+		for s in Sensor::all() {
+			assert_eq!(Sensor::hit(s.center()), Some(s), "{s}");
+		}
+		assert_eq!(Sensor::hit(Point::ORIGIN), Some(Sensor::C));
+		assert_eq!(Sensor::hit(Point::new(0., -1.1)), None);
+	}
+
+	#[test]
+	fn test_neighbors() {
+		let b1 = Sensor::new(SensorGroup::B, Key::K1);
+		let names = |s: Sensor| s.neighbors().iter().map(|s| s.to_string()).collect::<Vec<_>>();
+		assert_eq!(names(b1), ["A1", "E1", "E2", "B2", "B8", "C"]);
+		assert_eq!(names(Sensor::new(SensorGroup::D, Key::K1)), ["A1", "A8"]);
+		assert!(Sensor { group: SensorGroup::C, index: Some(Key::K2) }.is_adjacent(&b1));
+	}
+}
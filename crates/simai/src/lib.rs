@@ -1,2 +1,15 @@
+pub mod analysis;
+pub mod audio;
+pub mod convert;
 pub mod def;
+pub mod diff;
+pub mod layout;
+pub mod library;
+pub mod lint;
 pub mod parse;
+pub mod play;
+pub mod render;
+pub mod slide;
+pub mod timeline;
+pub mod transform;
+pub mod write;
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+
+use crate::def::*;
+use crate::parse::{Chart, Simai};
+use crate::timeline::{TimedNote, Timeline};
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+	Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// seconds rounded to the microsecond, so spellings that differ by float noise agree
+fn micros(secs: f64) -> f64 {
+	(secs * 1e6).round() / 1e6
+}
+
+/// A note written with every length and wait in seconds, and slide tracks in a fixed order.
+fn normalize(note: &TimedNote) -> String {
+	let len = |len: &Len| match len {
+		Len::Zero => Len::Zero,
+		len => Len::Abs(micros(len.to_abs(note.bpm).unwrap_or(0.))),
+	};
+	match &note.item {
+		Item::Hold(hold) => Hold { len: len(&hold.len), ..hold.clone() }.to_string(),
+		Item::TouchHold(touch) => TouchHold { len: len(&touch.len), ..touch.clone() }.to_string(),
+		Item::Slide(slide) => {
+			let mut tracks: Vec<String> = slide
+				.tracks
+				.iter()
+				.map(|track| {
+					let secs = track.segment_secs(note.bpm);
+					SlideTrack::Piecewise {
+						path: track
+							.path()
+							.iter()
+							.zip(secs)
+							.map(|((s, k), t)| ((*s).clone(), *k, Len::Abs(micros(t))))
+							.collect(),
+						wait: Wait::Abs(micros(track.wait_secs(note.bpm))),
+						style: track.style(),
+					}
+					.to_string()
+				})
+				.collect();
+			tracks.sort();
+			format!("{}{}{}", slide.key as u8, from_style(slide.star_style.bits()), tracks.join("*"))
+		}
+		item => item.to_string(),
+	}
+}
+
+/// The chart as one line per note and BPM change, with times in seconds, sorted. Charts that
+/// differ only in whitespace, comments, the order of style letters or notes at the same time,
+/// divisors or how lengths are written come out the same.
+pub fn normalized(chart: &Chart) -> String {
+	let timeline = Timeline::from_chart(chart);
+	let mut lines = vec![];
+	let mut last_bpm = None;
+	for change in timeline.tempo.changes() {
+		if last_bpm != Some(change.bpm) {
+			lines.push((micros(change.time), format!("({})", change.bpm)));
+		}
+		last_bpm = Some(change.bpm);
+	}
+	for note in &timeline.notes {
+		lines.push((micros(note.time), normalize(note)));
+	}
+	lines.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+	let mut out = String::new();
+	for (time, line) in lines {
+		let _ = writeln!(out, "{} {}", time, line);
+	}
+	out
+}
+
+/// SHA-256 of [`normalized`], in hex.
+pub fn notes_hash(chart: &Chart) -> String {
+	sha256_hex(normalized(chart).as_bytes())
+}
+
+/// Like [`notes_hash`], but also covering the title, artist and `&first` of the maidata and
+/// the designer and level of the chart. `None` if there is no `inote_N`.
+pub fn fingerprint(simai: &Simai, level: usize) -> Option<String> {
+	let chart = simai.chart.get(level)?.as_ref()?;
+	let text = |s: &Option<String>| s.as_deref().map_or(String::new(), |s| s.trim().to_string());
+	let mut out = String::new();
+	let _ = writeln!(out, "title {}", text(&simai.title));
+	let _ = writeln!(out, "artist {}", text(&simai.artist));
+	let _ = writeln!(out, "first {}", micros(simai.first.unwrap_or(0.)));
+	let _ = writeln!(out, "designer {}", text(&simai.designer[level]));
+	let _ = writeln!(out, "level {}", text(&simai.level[level]));
+	out.push_str(&normalized(chart));
+	Some(sha256_hex(out.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(text: &str) -> String {
+		notes_hash(&text.parse().unwrap())
+	}
+
+	#[test]
+	fn test_notes_hash() {
+		let a = hash("(120){4}1bx,2h[4:1],1-5[4:1],B1/C,E");
+		assert_eq!(a, hash("(120) {8} 1xb,, 2h[8:2],, 1-5[120#4:1],, C/B1 ,,E || comment"));
+		assert_eq!(a, hash("(120){4}1bx,2h[#0.5],1-5[0.5##0.5],C/B1,E"));
+		assert_ne!(a, hash("(120){4}1b,2h[4:1],1-5[4:1],B1/C,E"));
+		assert_ne!(a, hash("(121){4}1bx,2h[4:1],1-5[4:1],B1/C,E"));
+		assert_eq!(hash("(120){4}1-3*-5[4:1],E"), hash("(120){4}1-5*-3[4:1],E"));
+	}
+
+	#[test]
+	fn test_fingerprint() {
+		let simai: Simai = "&title=A\n&inote_5=(120){4}1,E\n".parse().unwrap();
+		let spaced: Simai = "&title= A \n&inote_5=(120){4}1,\nE\n".parse().unwrap();
+		let renamed: Simai = "&title=B\n&inote_5=(120){4}1,E\n".parse().unwrap();
+		assert_eq!(fingerprint(&simai, 5), fingerprint(&spaced, 5));
+		assert_ne!(fingerprint(&simai, 5), fingerprint(&renamed, 5));
+		assert_eq!(
+			notes_hash(simai.chart[5].as_ref().unwrap()),
+			notes_hash(renamed.chart[5].as_ref().unwrap())
+		);
+		assert_eq!(fingerprint(&simai, 4), None);
+	}
+}
@@ -0,0 +1,246 @@
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Query;
+use crate::def::*;
+use crate::parse::{Chart, Simai};
+use crate::timeline::Timeline;
+
+/// Numeric value of a level such as `13+`, which counts as 13.5.
+pub fn level_value(level: &str) -> Option<f64> {
+	let level = level.trim();
+	match level.strip_suffix('+') {
+		Some(base) => base.trim().parse::<f64>().ok().map(|l| l + 0.5),
+		None => level.parse().ok(),
+	}
+}
+
+/// What the index knows of one `inote_N`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChartInfo {
+	/// The `N` of `inote_N`.
+	pub difficulty: usize,
+	pub level: Option<String>,
+	pub designer: Option<String>,
+	/// Taps and slide stars.
+	pub taps: usize,
+	pub holds: usize,
+	/// Slide tracks.
+	pub slides: usize,
+	/// Touches and touch holds.
+	pub touches: usize,
+	/// Notes of any kind with a break, slide tracks counted on their own.
+	pub breaks: usize,
+	pub bpm_min: f64,
+	pub bpm_max: f64,
+}
+
+impl ChartInfo {
+	pub fn new(difficulty: usize, chart: &Chart) -> Self {
+		let mut info = ChartInfo { difficulty, ..Default::default() };
+		let is_break = |style: S| style & BREAK != 0;
+		let timeline = Timeline::from_chart(chart);
+		for note in &timeline.notes {
+			match &note.item {
+				Item::Tap(tap) => {
+					info.taps += 1;
+					info.breaks += is_break(tap.style.bits()) as usize;
+				}
+				Item::Hold(hold) => {
+					info.holds += 1;
+					info.breaks += is_break(hold.style.bits()) as usize;
+				}
+				Item::TouchTap(_) | Item::TouchHold(_) => info.touches += 1,
+				Item::Slide(slide) => {
+					let s = slide.star_style.bits();
+					if s & (REMOVE | SUDDEN) == 0 {
+						info.taps += 1;
+						info.breaks += is_break(s) as usize;
+					}
+					info.slides += slide.tracks.len();
+					info.breaks += slide.tracks.iter().filter(|t| is_break(t.style().bits())).count();
+				}
+				_ => {}
+			}
+		}
+		let bpms = timeline.tempo.changes().iter().map(|c| c.bpm);
+		info.bpm_min = bpms.clone().fold(f64::INFINITY, f64::min);
+		info.bpm_max = bpms.fold(0., f64::max);
+		if info.bpm_min.is_infinite() {
+			info.bpm_min = 0.;
+		}
+		info
+	}
+
+	pub fn notes(&self) -> usize {
+		self.taps + self.holds + self.slides + self.touches
+	}
+
+	pub fn level_value(&self) -> Option<f64> {
+		self.level.as_deref().and_then(level_value)
+	}
+}
+
+/// A maidata file in the index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Song {
+	pub path: PathBuf,
+	/// Modification time of the file in milliseconds since the Unix epoch.
+	pub modified: u64,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub charts: Vec<ChartInfo>,
+}
+
+impl Song {
+	pub fn new(path: PathBuf, modified: u64, simai: &Simai) -> Self {
+		let charts = (0..simai.chart.len())
+			.filter_map(|i| {
+				let chart = simai.chart[i].as_ref()?;
+				Some(ChartInfo {
+					level: simai.level[i].clone(),
+					designer: simai.designer[i].clone(),
+					..ChartInfo::new(i, chart)
+				})
+			})
+			.collect();
+		Song { path, modified, title: simai.title.clone(), artist: simai.artist.clone(), charts }
+	}
+}
+
+/// What [`Index::refresh`] did.
+#[derive(Debug, Default)]
+pub struct Refresh {
+	pub added: usize,
+	pub updated: usize,
+	pub removed: usize,
+	pub unchanged: usize,
+	/// Files that could not be read, which are left out of the index.
+	pub failed: Vec<(PathBuf, io::Error)>,
+}
+
+/// Metadata of every `maidata.txt` under a directory. It is plain data, meant to be kept on
+/// disk between runs and brought up to date with [`Index::refresh`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Index {
+	pub songs: Vec<Song>,
+}
+
+fn modified(path: &Path) -> io::Result<u64> {
+	let time = fs::metadata(path)?.modified()?;
+	Ok(time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64))
+}
+
+// every maidata.txt under `dir`, in no particular order
+fn find(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			find(&path, files)?;
+		} else if path.file_name().is_some_and(|n| n.eq_ignore_ascii_case("maidata.txt")) {
+			files.push(path);
+		}
+	}
+	Ok(())
+}
+
+impl Index {
+	/// Makes the index match the files under `root`, only parsing those that are new or
+	/// modified since they were indexed.
+	pub fn refresh(&mut self, root: &Path) -> io::Result<Refresh> {
+		let mut files = vec![];
+		find(root, &mut files)?;
+		files.sort();
+
+		let mut old: HashMap<PathBuf, Song> =
+			self.songs.drain(..).map(|s| (s.path.clone(), s)).collect();
+		let mut refresh = Refresh::default();
+		for path in files {
+			let song = modified(&path).and_then(|time| match old.remove(&path) {
+				Some(song) if song.modified == time => {
+					refresh.unchanged += 1;
+					Ok(song)
+				}
+				previous => {
+					let simai: Simai = fs::read_to_string(&path)?.parse().unwrap();
+					match previous {
+						Some(_) => refresh.updated += 1,
+						None => refresh.added += 1,
+					}
+					Ok(Song::new(path.clone(), time, &simai))
+				}
+			});
+			match song {
+				Ok(song) => self.songs.push(song),
+				Err(e) => refresh.failed.push((path, e)),
+			}
+		}
+		refresh.removed = old.len();
+		Ok(refresh)
+	}
+
+	/// Charts matching `query`, with their songs.
+	pub fn search(&self, query: &Query) -> Vec<(&Song, &ChartInfo)> {
+		self
+			.songs
+			.iter()
+			.flat_map(|song| song.charts.iter().map(move |chart| (song, chart)))
+			.filter(|(song, chart)| query.matches(song, chart))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_level_value() {
+		assert_eq!(level_value("13"), Some(13.));
+		assert_eq!(level_value("13+"), Some(13.5));
+		assert_eq!(level_value(" 12.7 "), Some(12.7));
+		assert_eq!(level_value("?"), None);
+	}
+
+	#[test]
+	fn test_chart_info() {
+		let chart: Chart = "(120){4}1b,2h[4:1],1-5[4:1]b,B1/C,(180)3,E".parse().unwrap();
+		let info = ChartInfo::new(5, &chart);
+		assert_eq!((info.taps, info.holds, info.slides, info.touches), (3, 1, 1, 2));
+		assert_eq!(info.breaks, 2);
+		assert_eq!(info.notes(), 7);
+		assert_eq!((info.bpm_min, info.bpm_max), (120., 180.));
+	}
+
+	#[test]
+	fn test_refresh() {
+		let root = std::env::temp_dir().join(format!("simai-index-{}", std::process::id()));
+		let (a, b) = (root.join("a/maidata.txt"), root.join("b/maidata.txt"));
+		for path in [&a, &b] {
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, "&title=a\n&lv_5=13\n&inote_5=(120){4}1,2,E\n").unwrap();
+		}
+		let counts = |r: Refresh| (r.added, r.updated, r.removed, r.unchanged);
+
+		let mut index = Index::default();
+		assert_eq!(counts(index.refresh(&root).unwrap()), (2, 0, 0, 0));
+		assert_eq!(counts(index.refresh(&root).unwrap()), (0, 0, 0, 2));
+
+		fs::write(&a, "&title=a\n&lv_5=14\n&inote_5=(120){4}1,E\n").unwrap();
+		let file = fs::File::options().write(true).open(&a).unwrap();
+		file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(1)).unwrap();
+		fs::remove_file(&b).unwrap();
+		assert_eq!(counts(index.refresh(&root).unwrap()), (0, 1, 1, 0));
+		assert_eq!(index.songs.len(), 1);
+		assert_eq!(index.songs[0].charts[0].level.as_deref(), Some("14"));
+		assert_eq!(index.search(&"taps=1".parse().unwrap()).len(), 1);
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
@@ -0,0 +1,7 @@
+mod fingerprint;
+mod index;
+mod query;
+
+pub use fingerprint::*;
+pub use index::*;
+pub use query::*;
@@ -0,0 +1,284 @@
+use std::{fmt::Display, str::FromStr};
+
+use chumsky::{extra::Err, prelude::*};
+
+use super::{ChartInfo, Song, level_value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+	Title,
+	Artist,
+	Designer,
+	Level,
+	/// The `N` of `inote_N`.
+	Difficulty,
+	/// Any BPM of the chart: `bpm>180` holds if it ever goes above 180, `bpm<100` if it ever goes
+	/// below 100, and `bpm=150` if 150 is within its range.
+	Bpm,
+	Notes,
+	Taps,
+	Holds,
+	Slides,
+	Touches,
+	Breaks,
+}
+
+impl Field {
+	pub const ALL: [Field; 12] = [
+		Field::Title,
+		Field::Artist,
+		Field::Designer,
+		Field::Level,
+		Field::Difficulty,
+		Field::Bpm,
+		Field::Notes,
+		Field::Taps,
+		Field::Holds,
+		Field::Slides,
+		Field::Touches,
+		Field::Breaks,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Field::Title => "title",
+			Field::Artist => "artist",
+			Field::Designer => "designer",
+			Field::Level => "level",
+			Field::Difficulty => "difficulty",
+			Field::Bpm => "bpm",
+			Field::Notes => "notes",
+			Field::Taps => "taps",
+			Field::Holds => "holds",
+			Field::Slides => "slides",
+			Field::Touches => "touches",
+			Field::Breaks => "breaks",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Field> {
+		Field::ALL.into_iter().find(|f| f.name() == name)
+	}
+
+	fn is_text(self) -> bool {
+		matches!(self, Field::Title | Field::Artist | Field::Designer)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+	Lt,
+	Le,
+	Gt,
+	Ge,
+	Eq,
+	Ne,
+	/// `~`, case insensitive substring match on text.
+	Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+	Number(f64),
+	Text(String),
+}
+
+/// A filter on charts, e.g. `level>=13 && bpm>180 && slides>200`. Comparisons combine with
+/// `&&`, `||`, `!` and parentheses; text is compared without case and may be quoted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+	Compare { field: Field, op: Op, value: Value },
+	And(Box<Query>, Box<Query>),
+	Or(Box<Query>, Box<Query>),
+	Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+	/// Byte offset into the query.
+	pub offset: usize,
+	pub message: String,
+}
+
+impl Display for QueryError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "at {}: {}", self.offset, self.message)
+	}
+}
+
+impl std::error::Error for QueryError {}
+
+// checks the value fits the field, turning levels like `13+` into numbers
+fn compare(field: Field, op: Op, value: Value) -> Result<Query, String> {
+	let value = match (field, value) {
+		(Field::Level, Value::Text(text)) => {
+			Value::Number(level_value(&text).ok_or(format!("'{}' is not a level", text))?)
+		}
+		(field, Value::Number(n)) if field.is_text() => Value::Text(n.to_string()),
+		(field, Value::Text(text)) if !field.is_text() => {
+			return Err(format!("{} needs a number, not '{}'", field.name(), text));
+		}
+		(_, value) => value,
+	};
+	let allowed = match op {
+		Op::Eq | Op::Ne => true,
+		Op::Contains => field.is_text(),
+		_ => !field.is_text(),
+	};
+	if !allowed {
+		return Err(format!("{} cannot be compared with {:?}", field.name(), op));
+	}
+	Ok(Query::Compare { field, op, value })
+}
+
+fn query<'a>() -> impl Parser<'a, &'a str, Query, Err<Rich<'a, char>>> {
+	let word = any()
+		.filter(|c: &char| c.is_alphanumeric() || "_+-.".contains(*c))
+		.repeated()
+		.at_least(1)
+		.to_slice()
+		.map(|s: &str| s.parse::<f64>().map_or(Value::Text(s.to_string()), Value::Number));
+	let quoted = none_of('"')
+		.repeated()
+		.to_slice()
+		.delimited_by(just('"'), just('"'))
+		.map(|s: &str| Value::Text(s.to_string()));
+	let value = choice((quoted, word)).padded().labelled("value");
+
+	let field = text::ident().to_slice().padded().labelled("field");
+	let op = choice((
+		just(">=").to(Op::Ge),
+		just("<=").to(Op::Le),
+		just("==").to(Op::Eq),
+		just("!=").to(Op::Ne),
+		just('>').to(Op::Gt),
+		just('<').to(Op::Lt),
+		just('=').to(Op::Eq),
+		just('~').to(Op::Contains),
+	))
+	.padded()
+	.labelled("comparison");
+	let comparison = field.then(op).then(value).try_map(|((field, op), value), span| {
+		let field = Field::from_name(field).ok_or(format!("unknown field '{}'", field));
+		field.and_then(|field| compare(field, op, value)).map_err(|m| Rich::custom(span, m))
+	});
+
+	recursive(|query| {
+		let atom = choice((comparison, query.delimited_by(just('(').padded(), just(')').padded())));
+		let not = just('!').padded().repeated().foldr(atom, |_, q| Query::Not(Box::new(q)));
+		let and = not.clone().foldl(just("&&").padded().ignore_then(not).repeated(), |a, b| {
+			Query::And(Box::new(a), Box::new(b))
+		});
+		and.clone().foldl(just("||").padded().ignore_then(and).repeated(), |a, b| {
+			Query::Or(Box::new(a), Box::new(b))
+		})
+	})
+	.then_ignore(end())
+}
+
+impl FromStr for Query {
+	type Err = QueryError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		query().parse(s).into_result().map_err(|errors| {
+			let e = &errors[0];
+			QueryError { offset: e.span().start, message: e.to_string() }
+		})
+	}
+}
+
+fn compare_numbers(a: f64, op: Op, b: f64) -> bool {
+	match op {
+		Op::Lt => a < b,
+		Op::Le => a <= b,
+		Op::Gt => a > b,
+		Op::Ge => a >= b,
+		Op::Eq => (a - b).abs() < 1e-9,
+		Op::Ne => (a - b).abs() >= 1e-9,
+		Op::Contains => false,
+	}
+}
+
+impl Query {
+	pub fn matches(&self, song: &Song, chart: &ChartInfo) -> bool {
+		match self {
+			Query::And(a, b) => a.matches(song, chart) && b.matches(song, chart),
+			Query::Or(a, b) => a.matches(song, chart) || b.matches(song, chart),
+			Query::Not(q) => !q.matches(song, chart),
+			Query::Compare { field, op, value: Value::Text(text) } => {
+				let Some(actual) = (match field {
+					Field::Title => song.title.as_deref(),
+					Field::Artist => song.artist.as_deref(),
+					_ => chart.designer.as_deref(),
+				}) else {
+					return *op == Op::Ne;
+				};
+				let (actual, text) = (actual.to_lowercase(), text.to_lowercase());
+				match op {
+					Op::Eq => actual == text,
+					Op::Ne => actual != text,
+					_ => actual.contains(&text),
+				}
+			}
+			Query::Compare { field: Field::Bpm, op, value: Value::Number(n) } => match op {
+				Op::Lt | Op::Le => compare_numbers(chart.bpm_min, *op, *n),
+				Op::Gt | Op::Ge => compare_numbers(chart.bpm_max, *op, *n),
+				_ => (chart.bpm_min - 1e-9 <= *n && *n <= chart.bpm_max + 1e-9) == (*op == Op::Eq),
+			},
+			Query::Compare { field, op, value: Value::Number(n) } => {
+				let actual = match field {
+					Field::Level => chart.level_value(),
+					Field::Difficulty => Some(chart.difficulty as f64),
+					Field::Notes => Some(chart.notes() as f64),
+					Field::Taps => Some(chart.taps as f64),
+					Field::Holds => Some(chart.holds as f64),
+					Field::Slides => Some(chart.slides as f64),
+					Field::Touches => Some(chart.touches as f64),
+					Field::Breaks => Some(chart.breaks as f64),
+					_ => None,
+				};
+				actual.is_some_and(|a| compare_numbers(a, *op, *n))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		let q: Query = "level>=13+ && !(title ~ \"sky\")".parse().unwrap();
+		let Query::And(a, b) = q else { panic!() };
+		assert_eq!(*a, Query::Compare { field: Field::Level, op: Op::Ge, value: Value::Number(13.5) });
+		assert!(matches!(*b, Query::Not(_)));
+		assert!("speed>3".parse::<Query>().unwrap_err().message.contains("unknown field"));
+		assert!("slides>many".parse::<Query>().is_err());
+		assert!("title>a".parse::<Query>().is_err());
+	}
+
+	#[test]
+	fn test_matches() {
+		let song = Song {
+			path: "maidata.txt".into(),
+			modified: 0,
+			title: Some("Blue Sky".to_string()),
+			artist: None,
+			charts: vec![],
+		};
+		let chart = ChartInfo {
+			difficulty: 5,
+			level: Some("13+".to_string()),
+			slides: 250,
+			bpm_min: 150.,
+			bpm_max: 200.,
+			..Default::default()
+		};
+		let matches = |q: &str| q.parse::<Query>().unwrap().matches(&song, &chart);
+		assert!(matches("level>=13 && bpm>180 && slides>200"));
+		assert!(matches("bpm<160 && bpm=175 && bpm!=210"));
+		assert!(!matches("level==13 || title=sky"));
+		assert!(matches("title~SKY && artist!=someone"));
+	}
+}
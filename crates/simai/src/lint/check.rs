@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use crate::lint::{Config, Rule, Severity};
+use crate::parse::{Chart, SimpleSpan};
+use crate::timeline::Timeline;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+	pub rule: Rule,
+	pub severity: Severity,
+	/// Where in the chart text, see [`crate::parse::line_col`].
+	pub span: SimpleSpan,
+	pub message: String,
+}
+
+impl Display for Lint {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)
+	}
+}
+
+/// Runs the rules enabled in `config`, ordered by position. Charts that failed to parse have
+/// nothing to check.
+pub fn lint(chart: &Chart, config: &Config) -> Vec<Lint> {
+	let Some(items) = &chart.notes else { return vec![] };
+	let timeline = Timeline::resolve(items);
+
+	let mut lints = vec![];
+	for rule in Rule::ALL {
+		let Some(severity) = config.severity(rule) else { continue };
+		for (span, message) in rule.check(items, &timeline, config) {
+			lints.push(Lint { rule, severity, span, message });
+		}
+	}
+	lints.sort_by_key(|l| (l.span.start, l.span.end));
+	lints
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::lint::Level;
+
+	fn names(text: &str, config: &Config) -> Vec<String> {
+		let chart: Chart = text.parse().unwrap();
+		lint(&chart, config).iter().map(|l| format!("{}@{}", l.rule, l.span.start)).collect()
+	}
+
+	#[test]
+	fn test_rules() {
+		let config = Config::default();
+		assert!(names("(120){4}1,2h[4:1],3,E", &config).is_empty());
+		assert_eq!(
+			names("(120){8}1h[2:1],1h[4:1],1,{8}2h,E", &config),
+			[
+				"overlapping-holds@16",
+				"tap-during-hold@24",
+				"too-many-hands@24",
+				"redundant-div@26",
+				"zero-length-hold@29"
+			]
+		);
+		assert_eq!(
+			names("(120){4}1-5[1.0##0.5],1,(150)", &config),
+			["slide-after-next-note@8", "bpm-without-notes@24", "missing-end@29"]
+		);
+		assert_eq!(names("(120){4}1,E,2,", &config), ["notes-after-end@12"]);
+		assert_eq!(names("(120){4}1h[2:1]/5h[2:1],3,E", &config), ["too-many-hands@24"]);
+	}
+
+	#[test]
+	fn test_config() {
+		let mut config = Config::default();
+		config.rules.insert("redundant-div".to_string(), Level::Off);
+		config.rules.insert("missing-end".to_string(), Level::Error);
+		config.rules.insert("typo".to_string(), Level::Info);
+
+		let chart: Chart = "(120){4}1,{4}2,".parse().unwrap();
+		let lints = lint(&chart, &config);
+		assert_eq!(lints.len(), 1);
+		assert_eq!(lints[0].to_string(), "error[missing-end]: chart does not end with E");
+		assert_eq!(config.unknown_rules(), ["typo"]);
+
+		let chart: Chart = "(120){4}1h[2:1]/5h[2:1],3,E".parse().unwrap();
+		config.limits.hands = 3;
+		assert!(lint(&chart, &config).is_empty());
+	}
+}
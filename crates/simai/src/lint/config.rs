@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Limits;
+use crate::lint::{Rule, Severity};
+
+/// Setting of a rule in a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+	Off,
+	Info,
+	Warning,
+	Error,
+}
+
+/// Which rules run and how severe they are, keyed by rule name, and the limits the
+/// playability rules check against. Rules not listed run at their default severity, e.g. in
+/// TOML:
+///
+/// ```toml
+/// [rules]
+/// redundant-div = "off"
+/// missing-end = "error"
+///
+/// [limits]
+/// hands = 3
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+	#[serde(default)]
+	pub rules: HashMap<String, Level>,
+	/// Used by `too-many-hands`.
+	#[serde(default)]
+	pub limits: Limits,
+}
+
+impl Config {
+	/// Severity of `rule`, or `None` if it is turned off.
+	pub fn severity(&self, rule: Rule) -> Option<Severity> {
+		match self.rules.get(rule.name()) {
+			None => Some(rule.default_severity()),
+			Some(Level::Off) => None,
+			Some(Level::Info) => Some(Severity::Info),
+			Some(Level::Warning) => Some(Severity::Warning),
+			Some(Level::Error) => Some(Severity::Error),
+		}
+	}
+
+	/// Names in the config that are not rules, most likely typos.
+	pub fn unknown_rules(&self) -> Vec<&str> {
+		let mut v: Vec<&str> = self
+			.rules
+			.keys()
+			.map(String::as_str)
+			.filter(|name| Rule::from_name(name).is_none())
+			.collect();
+		v.sort();
+		v
+	}
+}
@@ -0,0 +1,7 @@
+mod check;
+mod config;
+mod rule;
+
+pub use check::*;
+pub use config::*;
+pub use rule::*;
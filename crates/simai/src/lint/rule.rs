@@ -0,0 +1,248 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{Limits, overloads, pressed_key};
+use crate::def::*;
+use crate::lint::Config;
+use crate::parse::{SimpleSpan, Spanned};
+use crate::timeline::{SIMULTANEOUS, TimedNote, Timeline};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Info,
+	Warning,
+	Error,
+}
+
+impl Display for Severity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Severity::Info => write!(f, "info"),
+			Severity::Warning => write!(f, "warning"),
+			Severity::Error => write!(f, "error"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+	/// A hold starts on a key while another hold on it is still held.
+	OverlappingHolds,
+	/// A tap or star lands on a key that is being held.
+	TapDuringHold,
+	/// A slide only starts moving after the next note on its key.
+	SlideAfterNextNote,
+	/// A hold without length.
+	ZeroLengthHold,
+	/// A `{N}` or `{#x}` that repeats the current one.
+	RedundantDiv,
+	/// A BPM with no notes after it.
+	BpmWithoutNotes,
+	MissingEnd,
+	NotesAfterEnd,
+	/// More simultaneous inputs than the hands of [`Config::limits`] can play, see
+	/// [`crate::analysis::overloads`].
+	TooManyHands,
+}
+
+impl Rule {
+	pub const ALL: [Rule; 9] = [
+		Rule::OverlappingHolds,
+		Rule::TapDuringHold,
+		Rule::SlideAfterNextNote,
+		Rule::ZeroLengthHold,
+		Rule::RedundantDiv,
+		Rule::BpmWithoutNotes,
+		Rule::MissingEnd,
+		Rule::NotesAfterEnd,
+		Rule::TooManyHands,
+	];
+
+	/// Name used in config files and output.
+	pub fn name(self) -> &'static str {
+		match self {
+			Rule::OverlappingHolds => "overlapping-holds",
+			Rule::TapDuringHold => "tap-during-hold",
+			Rule::SlideAfterNextNote => "slide-after-next-note",
+			Rule::ZeroLengthHold => "zero-length-hold",
+			Rule::RedundantDiv => "redundant-div",
+			Rule::BpmWithoutNotes => "bpm-without-notes",
+			Rule::MissingEnd => "missing-end",
+			Rule::NotesAfterEnd => "notes-after-end",
+			Rule::TooManyHands => "too-many-hands",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Rule> {
+		Rule::ALL.into_iter().find(|r| r.name() == name)
+	}
+
+	pub fn default_severity(self) -> Severity {
+		match self {
+			Rule::OverlappingHolds | Rule::NotesAfterEnd => Severity::Error,
+			Rule::RedundantDiv => Severity::Info,
+			_ => Severity::Warning,
+		}
+	}
+
+	/// Spans and messages of the places in `items` breaking the rule, with the options of
+	/// `config`.
+	pub fn check(
+		self,
+		items: &[Spanned<Item>],
+		timeline: &Timeline,
+		config: &Config,
+	) -> Vec<(SimpleSpan, String)> {
+		match self {
+			Rule::OverlappingHolds => overlapping_holds(timeline),
+			Rule::TapDuringHold => tap_during_hold(timeline),
+			Rule::SlideAfterNextNote => slide_after_next_note(timeline),
+			Rule::ZeroLengthHold => zero_length_hold(timeline),
+			Rule::RedundantDiv => redundant_div(items),
+			Rule::BpmWithoutNotes => bpm_without_notes(items),
+			Rule::MissingEnd => missing_end(items),
+			Rule::NotesAfterEnd => notes_after_end(items),
+			Rule::TooManyHands => too_many_hands(timeline, &config.limits),
+		}
+	}
+}
+
+impl Display for Rule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+fn is_note(item: &Item) -> bool {
+	matches!(
+		item,
+		Item::Tap(_) | Item::Hold(_) | Item::TouchTap(_) | Item::TouchHold(_) | Item::Slide(_)
+	)
+}
+
+// holds with the key they are on
+fn holds(timeline: &Timeline) -> impl Iterator<Item = (&TimedNote, Key)> {
+	timeline.notes.iter().filter_map(|n| match &n.item {
+		Item::Hold(hold) => Some((n, hold.key)),
+		_ => None,
+	})
+}
+
+fn overlapping_holds(timeline: &Timeline) -> Vec<(SimpleSpan, String)> {
+	let mut ends: HashMap<Key, f64> = HashMap::new();
+	let mut v = vec![];
+	for (note, key) in holds(timeline) {
+		if let Some(end) = ends.get(&key)
+			&& note.time < end - SIMULTANEOUS
+		{
+			v.push((note.span, format!("hold on key {} starts before the previous one ends", key as u8)));
+		}
+		let end = ends.entry(key).or_insert(note.end_time());
+		*end = end.max(note.end_time());
+	}
+	v
+}
+
+fn tap_during_hold(timeline: &Timeline) -> Vec<(SimpleSpan, String)> {
+	let holds: Vec<_> = holds(timeline).collect();
+	let mut v = vec![];
+	for note in &timeline.notes {
+		if matches!(note.item, Item::Hold(_)) {
+			continue;
+		}
+		let Some(key) = pressed_key(&note.item) else { continue };
+		let held = holds.iter().any(|(hold, k)| {
+			*k == key
+				&& hold.time < note.time - SIMULTANEOUS
+				&& note.time < hold.end_time() - SIMULTANEOUS
+		});
+		if held {
+			v.push((note.span, format!("note on key {} while it is held", key as u8)));
+		}
+	}
+	v
+}
+
+fn slide_after_next_note(timeline: &Timeline) -> Vec<(SimpleSpan, String)> {
+	let mut v = vec![];
+	for (i, note) in timeline.notes.iter().enumerate() {
+		let Item::Slide(slide) = &note.item else { continue };
+		let Some(start) =
+			slide.tracks.iter().map(|t| note.time + t.wait_secs(note.bpm)).reduce(f64::min)
+		else {
+			continue;
+		};
+		let next = timeline.notes[i + 1..]
+			.iter()
+			.find(|n| n.time > note.time + SIMULTANEOUS && pressed_key(&n.item) == Some(slide.key));
+		if let Some(next) = next
+			&& next.time < start - SIMULTANEOUS
+		{
+			v.push((note.span, format!("slide starts after the next note on key {}", slide.key as u8)));
+		}
+	}
+	v
+}
+
+fn zero_length_hold(timeline: &Timeline) -> Vec<(SimpleSpan, String)> {
+	let mut v = vec![];
+	for note in &timeline.notes {
+		if matches!(note.item, Item::Hold(_) | Item::TouchHold(_))
+			&& note.end_time() - note.time < SIMULTANEOUS
+		{
+			v.push((note.span, "hold has no length".to_string()));
+		}
+	}
+	v
+}
+
+fn redundant_div(items: &[Spanned<Item>]) -> Vec<(SimpleSpan, String)> {
+	let mut v = vec![];
+	let mut current = None;
+	for item in items {
+		if matches!(item.inner, Item::Div(_) | Item::DivAbs(_)) {
+			if current == Some(&item.inner) {
+				v.push((item.span, format!("{} is already the current divisor", item.inner)));
+			}
+			current = Some(&item.inner);
+		}
+	}
+	v
+}
+
+fn bpm_without_notes(items: &[Spanned<Item>]) -> Vec<(SimpleSpan, String)> {
+	let mut v = vec![];
+	for (i, item) in items.iter().enumerate() {
+		if matches!(item.inner, Item::Bpm(_)) && !items[i + 1..].iter().any(|n| is_note(&n.inner)) {
+			v.push((item.span, format!("{} has no notes after it", item.inner)));
+		}
+	}
+	v
+}
+
+fn missing_end(items: &[Spanned<Item>]) -> Vec<(SimpleSpan, String)> {
+	if items.iter().any(|i| i.inner == Item::End) {
+		return vec![];
+	}
+	let end = items.last().map_or(0, |i| i.span.end);
+	vec![(SimpleSpan::from(end..end), "chart does not end with E".to_string())]
+}
+
+fn notes_after_end(items: &[Spanned<Item>]) -> Vec<(SimpleSpan, String)> {
+	let Some(end) = items.iter().position(|i| i.inner == Item::End) else { return vec![] };
+	items[end + 1..]
+		.iter()
+		.filter(|i| is_note(&i.inner))
+		.map(|i| (i.span, "note after E is never played".to_string()))
+		.collect()
+}
+
+// reported on the last note involved in the text, which is usually the one adding the load
+fn too_many_hands(timeline: &Timeline, limits: &Limits) -> Vec<(SimpleSpan, String)> {
+	overloads(timeline, limits)
+		.into_iter()
+		.filter_map(|load| Some((*load.spans.last()?, format!("needs {} hands at once", load.hands))))
+		.collect()
+}
@@ -259,6 +259,8 @@ pub fn simai<'a>() -> impl Parser<'a, &'a str, Vec<Spanned<Item>>, Err<Rich<'a,
 	let pre_items = choice((
 		bpm.then(div).map(|(b, d)| I::Items(vec![b, d])),
 		div.then(bpm).map(|(d, b)| I::Items(vec![b, d])),
+		bpm.then(div_abs).map(|(b, d)| I::Items(vec![b, d])),
+		div_abs.then(bpm).map(|(d, b)| I::Items(vec![b, d])),
 		bpm.map(I::Item),
 		div.map(I::Item),
 		div_abs.map(I::Item),
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, convert::Infallible, str::FromStr};
+use std::{collections::HashMap, convert::Infallible, fmt::Display, str::FromStr};
 
 use chumsky::{Parser, error::Rich};
 
@@ -38,6 +38,16 @@ impl FromStr for Chart {
 	}
 }
 
+/// Writes the parsed notes back to simai, or the raw text if parsing failed.
+impl Display for Chart {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.notes {
+			Some(notes) => write!(f, "{}", crate::write::chart(notes.iter().map(|n| &n.inner))),
+			None => write!(f, "{}", self.raw.trim()),
+		}
+	}
+}
+
 impl Simai {
 	pub fn new() -> Self {
 		Default::default()
@@ -130,6 +140,49 @@ impl FromStr for Simai {
 	}
 }
 
+/// Writes the container back to maidata. Charts are written from their parsed notes, and
+/// unknown commands are sorted by name so the output is stable.
+impl Display for Simai {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some(title) = &self.title {
+			writeln!(f, "&title={}", title)?;
+		}
+		if let Some(artist) = &self.artist {
+			writeln!(f, "&artist={}", artist)?;
+		}
+		if let Some(first) = self.first {
+			writeln!(f, "&first={}", first)?;
+		}
+
+		let mut rest: Vec<_> = self.rest_cmds.iter().collect();
+		rest.sort();
+		for (cmd, value) in rest {
+			writeln!(f, "&{}={}", cmd, value)?;
+		}
+
+		for i in 1..8 {
+			if let Some(designer) = &self.designer[i] {
+				writeln!(f, "&des_{}={}", i, designer)?;
+			}
+			if let Some(level) = &self.level[i] {
+				writeln!(f, "&lv_{}={}", i, level)?;
+			}
+			if let Some(chart) = &self.chart[i] {
+				writeln!(f, "&inote_{}=\n{}", i, chart)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Line and column of a byte offset into `raw`, both counted from 0 and the column in chars.
+pub fn line_col(raw: &str, offset: usize) -> (usize, usize) {
+	let before = &raw[..offset.min(raw.len())];
+	let line = before.matches('\n').count();
+	let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count());
+	(line, column)
+}
+
 pub fn process_comments(input: &str) -> (String, Vec<&str>) {
 	let mut stripped = String::with_capacity(input.len());
 	let mut comments = Vec::new();
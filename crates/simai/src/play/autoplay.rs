@@ -0,0 +1,164 @@
+use crate::def::*;
+use crate::slide::{Area, segment_areas};
+use crate::timeline::Timeline;
+
+use super::{Action, Input, Target};
+
+/// Plays a chart perfectly: every note is pressed at its exact time, holds are held to their
+/// end and slides are followed area by area at the speed the chart gives them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Autoplay {
+	/// Seconds a tap is held down for.
+	pub tap: f64,
+}
+
+impl Default for Autoplay {
+	fn default() -> Self {
+		Autoplay { tap: 0.05 }
+	}
+}
+
+/// When each area of a slide track is reached, or `None` if the track is impossible. Areas are
+/// spread evenly over the time of their segment.
+fn area_times(key: Key, track: &SlideTrack, start: f64, bpm: f64) -> Option<Vec<(f64, Area)>> {
+	let mut v = vec![];
+	let (mut from, mut time) = (key, start);
+	for ((shape, to), secs) in track.path().into_iter().zip(track.segment_secs(bpm)) {
+		let areas = segment_areas(shape, from, to)?;
+		let steps = (areas.len() - 1).max(1) as f64;
+		let skip = if v.is_empty() { 0 } else { 1 };
+		for (i, area) in areas.into_iter().enumerate().skip(skip) {
+			v.push((time + secs * i as f64 / steps, area));
+		}
+		from = to;
+		time += secs;
+	}
+	Some(v)
+}
+
+impl Autoplay {
+	/// Presses and releases in time order. A target pressed again while still held is released
+	/// and pressed at once, so every note gets a press of its own. Notes that never come or
+	/// never end, e.g. after a BPM of 0, are not played.
+	pub fn inputs(&self, timeline: &Timeline) -> Vec<Input> {
+		// (target, start, end) of every press a note asks for
+		let mut presses: Vec<(Target, f64, f64)> = vec![];
+		for note in timeline.reachable() {
+			let (time, end) = (note.time, note.end_time().max(note.time + self.tap));
+			match &note.item {
+				Item::Tap(tap) => presses.push((Target::Button(tap.key), time, time + self.tap)),
+				Item::Hold(hold) => presses.push((Target::Button(hold.key), time, end)),
+				Item::TouchTap(touch) => {
+					presses.push((Target::Sensor(touch.sensor), time, time + self.tap))
+				}
+				Item::TouchHold(touch) => presses.push((Target::Sensor(touch.sensor), time, end)),
+				Item::Slide(slide) => {
+					if slide.star_style.bits() & (REMOVE | SUDDEN) == 0 {
+						presses.push((Target::Button(slide.key), time, time + self.tap));
+					}
+					for track in &slide.tracks {
+						let start = time + track.wait_secs(note.bpm);
+						let Some(areas) = area_times(slide.key, track, start, note.bpm) else {
+							continue;
+						};
+						for (i, (t, area)) in areas.iter().enumerate() {
+							let next = areas.get(i + 1).map_or(t + self.tap, |n| n.0);
+							presses.extend(area.iter().map(|s| (Target::Sensor(*s), *t, next)));
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+		presses.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+		// targets in the order they are first pressed, with when they are held until
+		let mut held: Vec<(Target, Option<f64>)> = vec![];
+		let mut inputs = vec![];
+		let input = |time, action, target| Input { time, action, target };
+		for (target, start, end) in presses {
+			let i = match held.iter().position(|h| h.0 == target) {
+				Some(i) => i,
+				None => {
+					held.push((target, None));
+					held.len() - 1
+				}
+			};
+			match held[i].1 {
+				Some(until) if until >= start => {
+					inputs.push(input(start, Action::Release, target));
+					held[i].1 = Some(until.max(end));
+				}
+				Some(until) => {
+					inputs.push(input(until, Action::Release, target));
+					held[i].1 = Some(end);
+				}
+				None => held[i].1 = Some(end),
+			}
+			inputs.push(input(start, Action::Press, target));
+		}
+		for (target, until) in held {
+			if let Some(until) = until {
+				inputs.push(input(until, Action::Release, target));
+			}
+		}
+		// stable, so a release and press of one target at the same time keep their order
+		inputs.sort_by(|a, b| a.time.total_cmp(&b.time));
+		inputs
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn autoplay(text: &str) -> Vec<String> {
+		let timeline = Timeline::from_chart(&text.parse::<Chart>().unwrap());
+		Autoplay::default().inputs(&timeline).iter().map(|i| i.to_string()).collect()
+	}
+
+	#[test]
+	fn test_taps_and_holds() {
+		assert_eq!(
+			autoplay("(120){4}1,1h[4:1],B2,E"),
+			[
+				"0.0000 press 1",
+				"0.0500 release 1",
+				"0.5000 press 1",
+				"1.0000 press B2",
+				"1.0000 release 1",
+				"1.0500 release B2",
+			]
+		);
+		// a jack faster than a tap is pressed again before it would be released
+		assert_eq!(
+			autoplay("(120){64}1,1,E"),
+			["0.0000 press 1", "0.0312 release 1", "0.0312 press 1", "0.0813 release 1"]
+		);
+	}
+
+	#[test]
+	fn test_slide() {
+		// the next area is touched before the last one is let go, like a sliding finger
+		assert_eq!(
+			autoplay("(120){4}1-3[4:1],E"),
+			[
+				"0.0000 press 1",
+				"0.0500 release 1",
+				"0.5000 press A1",
+				"0.7500 press A2",
+				"0.7500 release A1",
+				"1.0000 press A3",
+				"1.0000 release A2",
+				"1.0500 release A3",
+			]
+		);
+	}
+
+	#[test]
+	fn test_unreachable_notes() {
+		// after a BPM of 0 the hold never ends and the next tap never comes
+		assert_eq!(autoplay("(120){4}1,(0)2h[4:1],3,E"), ["0.0000 press 1", "0.0500 release 1"]);
+	}
+}
@@ -0,0 +1,52 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::def::{Key, Sensor};
+
+/// Something a player can press: one of the eight buttons or a sensor of the touch panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+	Button(Key),
+	Sensor(Sensor),
+}
+
+impl Display for Target {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Target::Button(key) => write!(f, "{}", *key as u8),
+			Target::Sensor(sensor) => write!(f, "{}", sensor),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+	Press,
+	Release,
+}
+
+impl Display for Action {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Action::Press => write!(f, "press"),
+			Action::Release => write!(f, "release"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+	/// Seconds since the start of the chart.
+	pub time: f64,
+	pub action: Action,
+	pub target: Target,
+}
+
+impl Display for Input {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:.4} {} {}", self.time, self.action, self.target)
+	}
+}
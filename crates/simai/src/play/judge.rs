@@ -0,0 +1,389 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::def::*;
+use crate::slide::Area;
+use crate::timeline::{SIMULTANEOUS, Timeline};
+
+use super::{Action, Input, Target};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Judgment {
+	CriticalPerfect,
+	Perfect,
+	Great,
+	Good,
+	Miss,
+}
+
+impl Judgment {
+	pub const ALL: [Judgment; 5] =
+		[Judgment::CriticalPerfect, Judgment::Perfect, Judgment::Great, Judgment::Good, Judgment::Miss];
+}
+
+impl Display for Judgment {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Judgment::CriticalPerfect => write!(f, "critical perfect"),
+			Judgment::Perfect => write!(f, "perfect"),
+			Judgment::Great => write!(f, "great"),
+			Judgment::Good => write!(f, "good"),
+			Judgment::Miss => write!(f, "miss"),
+		}
+	}
+}
+
+/// What is judged. A slide note is judged as its star and once for each of its tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+	Tap,
+	Hold,
+	Slide,
+	Touch,
+	TouchHold,
+}
+
+impl Display for NoteKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			NoteKind::Tap => write!(f, "tap"),
+			NoteKind::Hold => write!(f, "hold"),
+			NoteKind::Slide => write!(f, "slide"),
+			NoteKind::Touch => write!(f, "touch"),
+			NoteKind::TouchHold => write!(f, "touch hold"),
+		}
+	}
+}
+
+/// How far off in seconds, either way, a hit may be for each judgment. Anything further is a
+/// miss.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Windows {
+	pub critical: f64,
+	pub perfect: f64,
+	pub great: f64,
+	pub good: f64,
+}
+
+impl Windows {
+	pub fn judge(&self, offset: f64) -> Judgment {
+		match offset.abs() {
+			o if o <= self.critical => Judgment::CriticalPerfect,
+			o if o <= self.perfect => Judgment::Perfect,
+			o if o <= self.great => Judgment::Great,
+			o if o <= self.good => Judgment::Good,
+			_ => Judgment::Miss,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Judged {
+	/// Index of the note in [`Timeline::notes`].
+	pub note: usize,
+	pub kind: NoteKind,
+	/// When the note should be hit, or for slides, finished.
+	pub time: f64,
+	#[serde(rename = "break")]
+	pub is_break: bool,
+	pub judgment: Judgment,
+	/// Seconds the hit was late by, negative if early. `None` for misses.
+	pub offset: Option<f64>,
+}
+
+/// Judges recorded inputs against a chart. The default windows are close to the arcade's, in
+/// frames at 60 fps.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Judge {
+	/// Taps, stars and the heads of holds.
+	pub tap: Windows,
+	/// Touches and the heads of touch holds.
+	pub touch: Windows,
+	/// How early a hold may be let go of, measured from its end.
+	pub release: Windows,
+	/// The time a slide is finished, measured from the end of its track.
+	pub slide: Windows,
+}
+
+const FRAME: f64 = 1. / 60.;
+
+impl Default for Judge {
+	fn default() -> Self {
+		Judge {
+			tap: Windows { critical: FRAME, perfect: 3. * FRAME, great: 6. * FRAME, good: 9. * FRAME },
+			touch: Windows {
+				critical: 9. * FRAME,
+				perfect: 10.5 * FRAME,
+				great: 12. * FRAME,
+				good: 15. * FRAME,
+			},
+			release: Windows {
+				critical: 6. * FRAME,
+				perfect: 9. * FRAME,
+				great: 12. * FRAME,
+				good: 18. * FRAME,
+			},
+			slide: Windows {
+				critical: 14. * FRAME,
+				perfect: 20. * FRAME,
+				great: 26. * FRAME,
+				good: 36. * FRAME,
+			},
+		}
+	}
+}
+
+struct Press {
+	target: Target,
+	time: f64,
+	/// When the target is let go of, infinite if never.
+	until: f64,
+	used: bool,
+}
+
+fn presses(inputs: &[Input]) -> Vec<Press> {
+	let mut inputs = inputs.to_vec();
+	inputs.sort_by(|a, b| a.time.total_cmp(&b.time));
+	let mut presses: Vec<Press> = vec![];
+	for input in inputs {
+		let open = presses.iter().rposition(|p| p.target == input.target && p.until.is_infinite());
+		match (input.action, open) {
+			(Action::Press, None) => presses.push(Press {
+				target: input.target,
+				time: input.time,
+				until: f64::INFINITY,
+				used: false,
+			}),
+			(Action::Release, Some(i)) => presses[i].until = input.time,
+			_ => {}
+		}
+	}
+	presses
+}
+
+// a button note can also be hit on the A sensor in front of its button
+fn on_key(key: Key) -> impl Fn(&Target) -> bool {
+	move |t| *t == Target::Button(key) || *t == Target::Sensor(Sensor::new(SensorGroup::A, key))
+}
+
+impl Judge {
+	/// The unused press of a matching target closest to `time`, marked used, with the
+	/// judgment it gets.
+	fn hit(
+		presses: &mut [Press],
+		windows: &Windows,
+		time: f64,
+		target: impl Fn(&Target) -> bool,
+	) -> Option<(usize, Judgment)> {
+		let i = presses
+			.iter()
+			.enumerate()
+			.filter(|(_, p)| !p.used && target(&p.target) && (p.time - time).abs() <= windows.good)
+			.min_by(|(_, a), (_, b)| (a.time - time).abs().total_cmp(&(b.time - time).abs()))?
+			.0;
+		presses[i].used = true;
+		Some((i, windows.judge(presses[i].time - time)))
+	}
+
+	/// When all areas of a slide track are touched in order, looking from `from` on.
+	fn slide_done(presses: &[Press], areas: &[Area], from: f64) -> Option<f64> {
+		let mut time = from;
+		for area in areas {
+			time = presses
+				.iter()
+				.filter(|p| p.until >= time && matches!(p.target, Target::Sensor(s) if area.contains(&s)))
+				.map(|p| p.time.max(time))
+				.min_by(f64::total_cmp)?;
+		}
+		Some(time)
+	}
+
+	/// Judges every note, in the order of the timeline. Slide tracks that cannot be played are
+	/// left out.
+	pub fn judge(&self, timeline: &Timeline, inputs: &[Input]) -> Vec<Judged> {
+		let mut presses = presses(inputs);
+		let mut judged = vec![];
+		for (i, note) in timeline.notes.iter().enumerate() {
+			let mut push = |kind, time, style: S, hit: Option<(usize, Judgment)>, presses: &[Press]| {
+				let (judgment, offset) = match hit {
+					Some((p, judgment)) => (judgment, Some(presses[p].time - time)),
+					None => (Judgment::Miss, None),
+				};
+				judged.push(Judged { note: i, kind, time, is_break: style & BREAK != 0, judgment, offset });
+			};
+			// EX notes are critical whenever they are hit at all
+			let ex = |hit: Option<(usize, Judgment)>, style: S| {
+				hit.map(|(p, judgment)| match style & EX != 0 && judgment != Judgment::Miss {
+					true => (p, Judgment::CriticalPerfect),
+					false => (p, judgment),
+				})
+			};
+			// the head judgment, made worse if let go of too early
+			let held = |hit: Option<(usize, Judgment)>, presses: &[Press]| {
+				hit.map(|(p, judgment)| {
+					let early = (presses[p].until - note.end_time()).min(0.);
+					(p, judgment.max(self.release.judge(early)))
+				})
+			};
+			match &note.item {
+				Item::Tap(tap) => {
+					let hit = Self::hit(&mut presses, &self.tap, note.time, on_key(tap.key));
+					let style = tap.style.bits();
+					push(NoteKind::Tap, note.time, style, ex(hit, style), &presses);
+				}
+				Item::Hold(hold) => {
+					let hit = Self::hit(&mut presses, &self.tap, note.time, on_key(hold.key));
+					let style = hold.style.bits();
+					push(NoteKind::Hold, note.time, style, held(ex(hit, style), &presses), &presses);
+				}
+				Item::TouchTap(touch) => {
+					let target = |t: &Target| *t == Target::Sensor(touch.sensor);
+					let hit = Self::hit(&mut presses, &self.touch, note.time, target);
+					push(NoteKind::Touch, note.time, NONE, hit, &presses);
+				}
+				Item::TouchHold(touch) => {
+					let target = |t: &Target| *t == Target::Sensor(touch.sensor);
+					let hit = Self::hit(&mut presses, &self.touch, note.time, target);
+					push(NoteKind::TouchHold, note.time, NONE, held(hit, &presses), &presses);
+				}
+				Item::Slide(slide) => {
+					let s = slide.star_style.bits();
+					if s & (REMOVE | SUDDEN) == 0 {
+						let hit = Self::hit(&mut presses, &self.tap, note.time, on_key(slide.key));
+						push(NoteKind::Tap, note.time, s, ex(hit, s), &presses);
+					}
+					for track in &slide.tracks {
+						let Some(areas) = track.areas(slide.key) else { continue };
+						let end = note.time
+							+ track.wait_secs(note.bpm)
+							+ track.segment_secs(note.bpm).iter().sum::<f64>();
+						let done = Self::slide_done(&presses, &areas, note.time)
+							.filter(|done| self.slide.judge(done - end) != Judgment::Miss);
+						let (judgment, offset) = match done {
+							Some(done) => (self.slide.judge(done - end), Some(done - end)),
+							None => (Judgment::Miss, None),
+						};
+						let is_break = track.style().bits() & BREAK != 0;
+						judged.push(Judged {
+							note: i,
+							kind: NoteKind::Slide,
+							time: end,
+							is_break,
+							judgment,
+							offset,
+						});
+					}
+				}
+				_ => {}
+			}
+		}
+		groups(timeline, &mut judged);
+		judged
+	}
+}
+
+/// Touches at the same time on adjacent sensors form a group. Once more than half of a group
+/// is hit, its missed touches count as hit with the worst judgment of the rest.
+fn groups(timeline: &Timeline, judged: &mut [Judged]) {
+	let touch = |j: &Judged| {
+		let Item::TouchTap(TouchTap { sensor, .. }) = timeline.notes[j.note].item else { return None };
+		Some(sensor)
+	};
+	let touches: Vec<usize> = (0..judged.len()).filter(|&j| touch(&judged[j]).is_some()).collect();
+	let mut group_of: Vec<usize> = (0..touches.len()).collect();
+	for a in 0..touches.len() {
+		for b in a + 1..touches.len() {
+			let (ja, jb) = (&judged[touches[a]], &judged[touches[b]]);
+			if (ja.time - jb.time).abs() < SIMULTANEOUS
+				&& touch(ja).unwrap().is_adjacent(&touch(jb).unwrap())
+			{
+				let (from, to) = (group_of[b], group_of[a]);
+				group_of.iter_mut().filter(|g| **g == from).for_each(|g| *g = to);
+			}
+		}
+	}
+	for group in 0..touches.len() {
+		let members: Vec<usize> =
+			(0..touches.len()).filter(|&t| group_of[t] == group).map(|t| touches[t]).collect();
+		let hit: Vec<Judgment> =
+			members.iter().map(|&j| judged[j].judgment).filter(|j| *j != Judgment::Miss).collect();
+		if members.len() < 2 || hit.len() * 2 <= members.len() {
+			continue;
+		}
+		let worst = hit.into_iter().max().unwrap();
+		for j in members {
+			if judged[j].judgment == Judgment::Miss {
+				judged[j].judgment = worst;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+	use crate::play::Autoplay;
+
+	fn judge(text: &str, edit: impl Fn(&mut Vec<Input>)) -> Vec<(NoteKind, Judgment)> {
+		let timeline = Timeline::from_chart(&text.parse::<Chart>().unwrap());
+		let mut inputs = Autoplay::default().inputs(&timeline);
+		edit(&mut inputs);
+		Judge::default().judge(&timeline, &inputs).iter().map(|j| (j.kind, j.judgment)).collect()
+	}
+
+	#[test]
+	fn test_autoplay_is_perfect() {
+		let judged = judge("(120){4}1,2h[4:1],1-5[4:1],C,B1/B2,Ch[2:1],E", |_| {});
+		assert_eq!(judged.len(), 8);
+		assert!(judged.iter().all(|j| j.1 == Judgment::CriticalPerfect));
+	}
+
+	#[test]
+	fn test_offsets() {
+		let late =
+			|by: f64| move |inputs: &mut Vec<Input>| inputs.iter_mut().for_each(|i| i.time += by);
+		assert_eq!(judge("(120){4}1,E", late(0.04)), [(NoteKind::Tap, Judgment::Perfect)]);
+		assert_eq!(judge("(120){4}1,E", late(-0.09)), [(NoteKind::Tap, Judgment::Great)]);
+		assert_eq!(judge("(120){4}1,E", late(0.2)), [(NoteKind::Tap, Judgment::Miss)]);
+		assert_eq!(judge("(120){4}1x,E", late(0.09)), [(NoteKind::Tap, Judgment::CriticalPerfect)]);
+	}
+
+	#[test]
+	fn test_release_and_slides() {
+		// let go of a hold half a second early
+		let early = |inputs: &mut Vec<Input>| inputs[1].time -= 0.5;
+		assert_eq!(judge("(120){4}1h[1:1],E", early), [(NoteKind::Hold, Judgment::Miss)]);
+		// an EX head does not make up for letting go
+		let soon = |inputs: &mut Vec<Input>| inputs[1].time -= 15. * FRAME;
+		assert_eq!(judge("(120){4}1xh[1:1],E", soon), [(NoteKind::Hold, Judgment::Good)]);
+		assert_eq!(judge("(120){4}1xh[1:1],E", |_| {}), [(NoteKind::Hold, Judgment::CriticalPerfect)]);
+		// a slide missing its middle area is never finished
+		let skip = |inputs: &mut Vec<Input>| {
+			inputs.retain(|i| i.target != Target::Sensor(Sensor::new(SensorGroup::A, Key::K2)))
+		};
+		assert_eq!(
+			judge("(120){4}1-3[4:1],E", skip),
+			[(NoteKind::Tap, Judgment::CriticalPerfect), (NoteKind::Slide, Judgment::Miss)]
+		);
+	}
+
+	#[test]
+	fn test_touch_groups() {
+		let only_b1 = |inputs: &mut Vec<Input>| {
+			inputs.retain(|i| i.target != Target::Sensor(Sensor::new(SensorGroup::B, Key::K2)))
+		};
+		// one of two is not more than half
+		assert_eq!(
+			judge("(120){4}B1/B2,E", only_b1),
+			[(NoteKind::Touch, Judgment::CriticalPerfect), (NoteKind::Touch, Judgment::Miss)]
+		);
+		assert_eq!(
+			judge("(120){4}B1/B2/C,E", only_b1),
+			[(NoteKind::Touch, Judgment::CriticalPerfect); 3]
+		);
+	}
+}
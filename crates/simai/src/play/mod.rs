@@ -0,0 +1,11 @@
+mod autoplay;
+mod input;
+mod judge;
+mod replay;
+mod score;
+
+pub use autoplay::*;
+pub use input::*;
+pub use judge::*;
+pub use replay::*;
+pub use score::*;
@@ -0,0 +1,285 @@
+use std::{
+	fmt::Display,
+	io::{self, Read, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::def::{Key, Sensor, SensorGroup};
+use crate::library::sha256_hex;
+use crate::parse::Chart;
+
+use super::{Action, Input, Target};
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"SMRP";
+pub const REPLAY_VERSION: u16 = 1;
+
+/// SHA-256 of the text of a chart, without comments and surrounding whitespace, in hex.
+pub fn chart_hash(chart: &Chart) -> String {
+	sha256_hex(chart.raw.trim().as_bytes())
+}
+
+/// Inputs recorded on a chart. It is stored either as JSON through serde, or in a compact
+/// binary form with [`Replay::write`] and [`Replay::read`] that keeps times to the microsecond.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+	pub version: u16,
+	/// [`chart_hash`] of the chart played.
+	pub chart: String,
+	/// The `N` of `inote_N`.
+	pub level: u8,
+	/// `&first` of the maidata when it was played.
+	pub first: f64,
+	/// Seconds added to every input before judging, as set by the player.
+	pub offset: f64,
+	/// Inputs in time order, in seconds since the start of the chart.
+	pub inputs: Vec<Input>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+	Io(io::Error),
+	/// The data does not start with [`REPLAY_MAGIC`].
+	NotReplay,
+	UnsupportedVersion(u16),
+	BadTarget(u8),
+	/// The replay was recorded on another chart or level.
+	ChartMismatch {
+		expected: String,
+		found: String,
+	},
+}
+
+impl Display for ReplayError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ReplayError::Io(e) => write!(f, "{}", e),
+			ReplayError::NotReplay => write!(f, "not a replay"),
+			ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {}", v),
+			ReplayError::BadTarget(t) => write!(f, "unknown input target {}", t),
+			ReplayError::ChartMismatch { expected, found } => {
+				write!(f, "replay is of chart {}, not {}", found, expected)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+	fn from(e: io::Error) -> Self {
+		ReplayError::Io(e)
+	}
+}
+
+const GROUPS: [SensorGroup; 5] =
+	[SensorGroup::A, SensorGroup::B, SensorGroup::C, SensorGroup::D, SensorGroup::E];
+
+// buttons are 0 to 7, sensors 8 to 47 by group then key, and the center 48
+fn target_code(target: Target) -> u8 {
+	match target {
+		Target::Button(key) => key as u8 - 1,
+		Target::Sensor(Sensor { index: None, .. }) => 48,
+		Target::Sensor(Sensor { group, index: Some(key) }) => {
+			let g = GROUPS.iter().position(|g| *g == group).unwrap() as u8;
+			8 + g * 8 + key as u8 - 1
+		}
+	}
+}
+
+fn code_target(code: u8) -> Option<Target> {
+	Some(match code {
+		0..8 => Target::Button(Key::ALL[code as usize]),
+		8..48 => {
+			let (g, k) = ((code - 8) / 8, (code - 8) % 8);
+			Target::Sensor(Sensor::new(GROUPS[g as usize], Key::ALL[k as usize]))
+		}
+		48 => Target::Sensor(Sensor::C),
+		_ => return None,
+	})
+}
+
+fn write_varint(w: &mut impl Write, mut n: u64) -> io::Result<()> {
+	loop {
+		let byte = (n & 0x7f) as u8;
+		n >>= 7;
+		if n == 0 {
+			return w.write_all(&[byte]);
+		}
+		w.write_all(&[byte | 0x80])?;
+	}
+}
+
+fn read_varint(r: &mut impl Read) -> io::Result<u64> {
+	let mut n = 0;
+	for shift in (0..64).step_by(7) {
+		let byte = read_array::<1>(r)?[0];
+		n |= ((byte & 0x7f) as u64) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(n);
+		}
+	}
+	Err(io::Error::new(io::ErrorKind::InvalidData, "varint too long"))
+}
+
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+	let mut buf = [0; N];
+	r.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+impl Replay {
+	pub fn new(chart: &Chart, level: u8, first: f64, inputs: Vec<Input>) -> Self {
+		Replay { version: REPLAY_VERSION, chart: chart_hash(chart), level, first, offset: 0., inputs }
+	}
+
+	/// Inputs as they are judged, with [`Replay::offset`] applied.
+	pub fn judged_inputs(&self) -> Vec<Input> {
+		self.inputs.iter().map(|i| Input { time: i.time + self.offset, ..*i }).collect()
+	}
+
+	/// Checks the replay can be read by this version and was recorded on `chart`.
+	pub fn check(&self, chart: &Chart, level: u8) -> Result<(), ReplayError> {
+		if !(1..=REPLAY_VERSION).contains(&self.version) {
+			return Err(ReplayError::UnsupportedVersion(self.version));
+		}
+		let expected = format!("{} inote_{}", chart_hash(chart), level);
+		let found = format!("{} inote_{}", self.chart, self.level);
+		if expected != found {
+			return Err(ReplayError::ChartMismatch { expected, found });
+		}
+		Ok(())
+	}
+
+	/// Writes the binary form: magic, version, chart hash, level, `&first` and offset, then the
+	/// inputs as a count followed by each one's time since the last in zigzag encoded
+	/// microseconds and a byte of action and target. Numbers are little endian. Input times
+	/// must be finite and within about 290000 years of each other.
+	pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+		w.write_all(REPLAY_MAGIC)?;
+		w.write_all(&REPLAY_VERSION.to_le_bytes())?;
+		let hash: Vec<u8> = (0..self.chart.len() / 2)
+			.filter_map(|i| u8::from_str_radix(self.chart.get(2 * i..2 * i + 2)?, 16).ok())
+			.collect();
+		if hash.len() != 32 {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "chart hash is not SHA-256"));
+		}
+		w.write_all(&hash)?;
+		w.write_all(&[self.level])?;
+		w.write_all(&self.first.to_le_bytes())?;
+		w.write_all(&self.offset.to_le_bytes())?;
+		write_varint(w, self.inputs.len() as u64)?;
+		let out_of_range = || io::Error::new(io::ErrorKind::InvalidInput, "input time out of range");
+		let mut last: i64 = 0;
+		for input in &self.inputs {
+			let micros = (input.time * 1e6).round();
+			// the bounds are exact powers of two, so the comparison is too
+			if !(-(2f64.powi(63))..2f64.powi(63)).contains(&micros) {
+				return Err(out_of_range());
+			}
+			let time = micros as i64;
+			let delta = time.checked_sub(last).ok_or_else(out_of_range)?;
+			write_varint(w, ((delta << 1) ^ (delta >> 63)) as u64)?;
+			let release = if input.action == Action::Release { 0x80 } else { 0 };
+			w.write_all(&[release | target_code(input.target)])?;
+			last = time;
+		}
+		Ok(())
+	}
+
+	pub fn read(r: &mut impl Read) -> Result<Self, ReplayError> {
+		if &read_array::<4>(r)? != REPLAY_MAGIC {
+			return Err(ReplayError::NotReplay);
+		}
+		let version = u16::from_le_bytes(read_array(r)?);
+		if !(1..=REPLAY_VERSION).contains(&version) {
+			return Err(ReplayError::UnsupportedVersion(version));
+		}
+		let chart = read_array::<32>(r)?.iter().map(|b| format!("{:02x}", b)).collect();
+		let level = read_array::<1>(r)?[0];
+		let first = f64::from_le_bytes(read_array(r)?);
+		let offset = f64::from_le_bytes(read_array(r)?);
+		let count = read_varint(r)?;
+		let mut inputs = vec![];
+		let mut last: i64 = 0;
+		for _ in 0..count {
+			let zigzag = read_varint(r)?;
+			last = last
+				.checked_add((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64))
+				.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "input time out of range"))?;
+			let byte = read_array::<1>(r)?[0];
+			let target = code_target(byte & 0x7f).ok_or(ReplayError::BadTarget(byte & 0x7f))?;
+			let action = if byte & 0x80 != 0 { Action::Release } else { Action::Press };
+			inputs.push(Input { time: last as f64 / 1e6, action, target });
+		}
+		Ok(Replay { version, chart, level, first, offset, inputs })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::play::Autoplay;
+	use crate::timeline::Timeline;
+
+	#[test]
+	fn test_binary() {
+		let chart: Chart = "(120){4}1,2h[4:1],1-5[4:1],C,B1/E8,E".parse().unwrap();
+		let inputs = Autoplay::default().inputs(&Timeline::from_chart(&chart));
+		let mut replay = Replay::new(&chart, 5, 1.25, inputs);
+		replay.offset = -0.01;
+		replay.inputs[0].time = -0.5;
+
+		let mut bytes = vec![];
+		replay.write(&mut bytes).unwrap();
+		// header, then at most 3 bytes of time and one of target per input here
+		assert!(bytes.len() <= 56 + replay.inputs.len() * 4);
+		let read = Replay::read(&mut bytes.as_slice()).unwrap();
+		assert_eq!(read, replay);
+
+		assert!(read.check(&chart, 5).is_ok());
+		assert!(read.check(&chart, 4).is_err());
+		let other: Chart = "(120){4}1,E".parse().unwrap();
+		assert!(matches!(read.check(&other, 5), Err(ReplayError::ChartMismatch { .. })));
+		assert!(matches!(Replay::read(&mut &b"RIFF...."[..]), Err(ReplayError::NotReplay)));
+		assert!(matches!(Replay::read(&mut &bytes[..50]), Err(ReplayError::Io(_))));
+	}
+
+	#[test]
+	fn test_bad_binary() {
+		let chart: Chart = "(120){4}1,E".parse().unwrap();
+		let mut replay = Replay::new(&chart, 5, 0., vec![]);
+		let mut header = vec![];
+		replay.write(&mut header).unwrap();
+		header.pop();
+
+		// two inputs each i64::MAX microseconds after the last
+		let mut bytes = header.clone();
+		write_varint(&mut bytes, 2).unwrap();
+		for _ in 0..2 {
+			write_varint(&mut bytes, u64::MAX - 1).unwrap();
+			bytes.push(0);
+		}
+		let err = Replay::read(&mut bytes.as_slice());
+		assert!(matches!(err, Err(ReplayError::Io(e)) if e.kind() == io::ErrorKind::InvalidData));
+
+		let mut bytes = header.clone();
+		bytes[4..6].copy_from_slice(&0u16.to_le_bytes());
+		assert!(matches!(Replay::read(&mut bytes.as_slice()), Err(ReplayError::UnsupportedVersion(0))));
+
+		let press = |time| Input { time, action: Action::Press, target: Target::Button(Key::K1) };
+		for times in [vec![f64::NAN], vec![f64::INFINITY], vec![-9e12, 9e12]] {
+			replay.inputs = times.into_iter().map(press).collect();
+			let err = replay.write(&mut vec![]).unwrap_err();
+			assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+		}
+	}
+
+	#[test]
+	fn test_targets() {
+		for code in 0..49 {
+			assert_eq!(code_target(code).map(target_code), Some(code));
+		}
+		assert_eq!(code_target(49), None);
+	}
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Judged, Judgment, NoteKind};
+
+/// DX score of one judgment: 3 for a critical perfect down to 0 for a good or a miss.
+pub fn dx_score(judgment: Judgment) -> u32 {
+	match judgment {
+		Judgment::CriticalPerfect => 3,
+		Judgment::Perfect => 2,
+		Judgment::Great => 1,
+		Judgment::Good | Judgment::Miss => 0,
+	}
+}
+
+/// How much a note counts towards the achievement rate, relative to a tap.
+pub fn weight(kind: NoteKind, is_break: bool) -> f64 {
+	match kind {
+		_ if is_break => 5.,
+		NoteKind::Tap | NoteKind::Touch => 1.,
+		NoteKind::Hold | NoteKind::TouchHold => 2.,
+		NoteKind::Slide => 3.,
+	}
+}
+
+// share of its weight a note scores, and for breaks, share of the break bonus
+fn rate(judgment: Judgment, is_break: bool) -> f64 {
+	match judgment {
+		Judgment::CriticalPerfect | Judgment::Perfect => 1.,
+		Judgment::Great => 0.8,
+		Judgment::Good if is_break => 0.4,
+		Judgment::Good => 0.5,
+		Judgment::Miss => 0.,
+	}
+}
+
+fn bonus(judgment: Judgment) -> f64 {
+	match judgment {
+		Judgment::CriticalPerfect => 1.,
+		Judgment::Perfect => 0.75,
+		Judgment::Great => 0.4,
+		Judgment::Good => 0.3,
+		Judgment::Miss => 0.,
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+	pub critical_perfect: usize,
+	pub perfect: usize,
+	pub great: usize,
+	pub good: usize,
+	pub miss: usize,
+}
+
+impl Counts {
+	pub fn get(&self, judgment: Judgment) -> usize {
+		match judgment {
+			Judgment::CriticalPerfect => self.critical_perfect,
+			Judgment::Perfect => self.perfect,
+			Judgment::Great => self.great,
+			Judgment::Good => self.good,
+			Judgment::Miss => self.miss,
+		}
+	}
+
+	fn add(&mut self, judgment: Judgment) {
+		*match judgment {
+			Judgment::CriticalPerfect => &mut self.critical_perfect,
+			Judgment::Perfect => &mut self.perfect,
+			Judgment::Great => &mut self.great,
+			Judgment::Good => &mut self.good,
+			Judgment::Miss => &mut self.miss,
+		} += 1;
+	}
+}
+
+/// The result of a play.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scorecard {
+	pub counts: Counts,
+	pub breaks: Counts,
+	pub max_combo: usize,
+	pub dx_score: u32,
+	pub max_dx_score: u32,
+	/// Achievement rate in percent: up to 100 from the notes plus up to 1 of break bonus.
+	pub achievement: f64,
+}
+
+impl Scorecard {
+	pub fn new(judged: &[Judged]) -> Self {
+		let mut card = Scorecard::default();
+		let mut notes: Vec<&Judged> = judged.iter().collect();
+		notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+		let mut combo = 0;
+		let (mut score, mut total, mut bonuses) = (0., 0., 0.);
+		for j in notes {
+			card.counts.add(j.judgment);
+			if j.is_break {
+				card.breaks.add(j.judgment);
+				bonuses += bonus(j.judgment);
+			}
+			combo = if j.judgment == Judgment::Miss { 0 } else { combo + 1 };
+			card.max_combo = card.max_combo.max(combo);
+			card.dx_score += dx_score(j.judgment);
+			card.max_dx_score += dx_score(Judgment::CriticalPerfect);
+			score += weight(j.kind, j.is_break) * rate(j.judgment, j.is_break);
+			total += weight(j.kind, j.is_break);
+		}
+		let breaks: usize = Judgment::ALL.iter().map(|j| card.breaks.get(*j)).sum();
+		if total > 0. {
+			card.achievement = score / total * 100.;
+		}
+		if breaks > 0 {
+			card.achievement += bonuses / breaks as f64;
+		}
+		card
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn judged(kind: NoteKind, is_break: bool, judgment: Judgment) -> Judged {
+		Judged { note: 0, kind, time: 0., is_break, judgment, offset: None }
+	}
+
+	#[test]
+	fn test_scorecard() {
+		let card = Scorecard::new(&[
+			judged(NoteKind::Tap, false, Judgment::CriticalPerfect),
+			judged(NoteKind::Slide, false, Judgment::Great),
+			judged(NoteKind::Tap, true, Judgment::Perfect),
+			judged(NoteKind::Hold, false, Judgment::Miss),
+			judged(NoteKind::Touch, false, Judgment::Good),
+		]);
+		assert_eq!(card.counts, Counts { critical_perfect: 1, perfect: 1, great: 1, good: 1, miss: 1 });
+		assert_eq!(card.breaks.perfect, 1);
+		assert_eq!(card.max_combo, 3);
+		assert_eq!((card.dx_score, card.max_dx_score), (6, 15));
+		// (1 + 3 * 0.8 + 5 + 0 + 0.5) / 12 of 100, and 0.75 of the break bonus
+		assert!((card.achievement - (8.9 / 12. * 100. + 0.75)).abs() < 1e-9);
+		assert_eq!(Scorecard::new(&[]).achievement, 0.);
+	}
+}
@@ -0,0 +1,171 @@
+use std::{fmt::Write, ops::RangeInclusive};
+
+use crate::def::*;
+use crate::timeline::{PSEUDO_TICK, Step};
+
+const EPS: f64 = 1e-9;
+
+/// Prints a chart as text, one row per tick with a column per key, then the touches and the
+/// notes written out. Keys show `o` for taps, `h` for holds with `|` while held and `*` for
+/// stars; breaks are in upper case, with `@` for break stars.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preview {
+	/// Measures to print, counted from 1. All of them if `None`.
+	pub measures: Option<RangeInclusive<usize>>,
+}
+
+#[derive(Default)]
+struct Row {
+	/// Position in measures.
+	pos: f64,
+	keys: [Option<char>; 8],
+	touches: Vec<String>,
+	notes: Vec<String>,
+}
+
+fn symbol(item: &Item) -> Option<(Key, char)> {
+	let upper = |s: S, c: char| if s & BREAK != 0 { c.to_ascii_uppercase() } else { c };
+	Some(match item {
+		Item::Tap(tap) => {
+			let s = tap.style.bits();
+			match s & (TAP_STAR | NAKED_STAR) != 0 {
+				true if s & BREAK != 0 => (tap.key, '@'),
+				true => (tap.key, '*'),
+				false => (tap.key, upper(s, 'o')),
+			}
+		}
+		Item::Hold(hold) => (hold.key, upper(hold.style.bits(), 'h')),
+		Item::Slide(slide) => {
+			let s = slide.star_style.bits();
+			if s & (REMOVE | SUDDEN) != 0 {
+				return None;
+			}
+			(slide.key, if s & BREAK != 0 { '@' } else { '*' })
+		}
+		_ => return None,
+	})
+}
+
+impl Preview {
+	pub fn render<'a>(&self, items: impl IntoIterator<Item = &'a Item>) -> String {
+		let mut rows: Vec<Row> = vec![];
+		let mut row = Row::default();
+		let mut bpm = None;
+		let mut step = None;
+		// key index, start and end of every hold, in measures
+		let mut holds: Vec<(usize, f64, f64)> = vec![];
+
+		for item in items {
+			match item {
+				Item::Bpm(Bpm(b)) => bpm = Some(*b),
+				Item::Div(Div(d)) => step = Some(Step::Div(*d)),
+				Item::DivAbs(DivAbs(abs)) => step = Some(Step::Abs(*abs)),
+				_ => {}
+			}
+			match item {
+				Item::Bpm(_) | Item::Div(_) | Item::DivAbs(_) => row.notes.push(item.to_string()),
+				// `,,,` is three ticks, each getting its row
+				Item::Tick(Tick(n)) => {
+					let advance = match (step, bpm) {
+						(Some(Step::Div(d)), _) => 1. / d as f64,
+						(Some(Step::Abs(abs)), Some(bpm)) => abs * bpm / 240.,
+						_ => 0.,
+					};
+					for _ in 0..*n {
+						let pos = row.pos + advance;
+						rows.push(std::mem::replace(&mut row, Row { pos, ..Row::default() }));
+					}
+				}
+				Item::PseudoTick(PseudoTick(n)) => {
+					let pos = row.pos + *n as f64 * PSEUDO_TICK;
+					rows.push(std::mem::replace(&mut row, Row { pos, ..Row::default() }));
+				}
+				Item::End => {
+					row.notes.push("E".to_string());
+					break;
+				}
+				Item::Error => {}
+				Item::TouchTap(_) | Item::TouchHold(_) => row.touches.push(item.to_string()),
+				note => {
+					if let Some((key, c)) = symbol(note) {
+						row.keys[key as usize - 1] = Some(c);
+					}
+					match note {
+						Item::Hold(hold) => {
+							let len = bpm.and_then(|bpm| Some(hold.len.to_abs(bpm)? * bpm / 240.));
+							holds.push((hold.key as usize - 1, row.pos, row.pos + len.unwrap_or(0.)));
+						}
+						Item::Slide(_) => row.notes.push(note.to_string()),
+						_ => {}
+					}
+				}
+			}
+		}
+		if row.keys.iter().any(Option::is_some) || !row.touches.is_empty() || !row.notes.is_empty() {
+			rows.push(row);
+		}
+
+		for row in &mut rows {
+			for &(key, start, end) in &holds {
+				if row.keys[key].is_none() && start + EPS < row.pos && row.pos < end - EPS {
+					row.keys[key] = Some('|');
+				}
+			}
+		}
+		self.print(&rows)
+	}
+
+	fn print(&self, rows: &[Row]) -> String {
+		let mut out = String::new();
+		let mut last_measure = None;
+		for row in rows {
+			let measure = (row.pos + EPS).floor() as usize + 1;
+			if self.measures.as_ref().is_some_and(|m| !m.contains(&measure)) {
+				continue;
+			}
+			let label = if last_measure != Some(measure) {
+				if last_measure.is_some() {
+					out.push_str("-----+--------+\n");
+				}
+				measure.to_string()
+			} else {
+				String::new()
+			};
+			last_measure = Some(measure);
+			let keys: String = row.keys.iter().map(|k| k.unwrap_or('.')).collect();
+			let text: Vec<&str> = row.touches.iter().chain(&row.notes).map(String::as_str).collect();
+			let line = format!("{:>4} |{}| {}", label, keys, text.join(" "));
+			let _ = writeln!(out, "{}", line.trim_end());
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse::Chart;
+
+	fn preview(text: &str, measures: Option<RangeInclusive<usize>>) -> String {
+		let chart: Chart = text.parse().unwrap();
+		let items = chart.notes.unwrap();
+		Preview { measures }.render(items.iter().map(|i| &i.inner))
+	}
+
+	#[test]
+	fn test_preview() {
+		assert_eq!(
+			preview("(120){4}1,2b/B1,3h[2:1],,{2}1-5[4:1],(150)4$,E", None),
+			"   1 |o.......| (120) {4}\n\
+			 \x20    |.O......| B1\n\
+			 \x20    |..h.....|\n\
+			 \x20    |..|.....|\n\
+			 -----+--------+\n\
+			 \x20  2 |*.......| {2} 1-5[4:1]\n\
+			 \x20    |...*....| (150)\n\
+			 -----+--------+\n\
+			 \x20  3 |........| E\n"
+		);
+		assert_eq!(preview("(120){1}1,2,3,E", Some(2..=2)), "   2 |.o......|\n");
+	}
+}
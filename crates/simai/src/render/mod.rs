@@ -0,0 +1,8 @@
+mod ascii;
+mod playfield;
+mod sheet;
+mod svg;
+
+pub use ascii::*;
+pub use playfield::*;
+pub use sheet::*;
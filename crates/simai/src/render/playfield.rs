@@ -0,0 +1,206 @@
+use crate::def::*;
+use crate::layout::Point;
+use crate::timeline::{SIMULTANEOUS, TimedNote, Timeline};
+
+use super::svg::*;
+
+// radius notes appear at before moving out to the ring
+const SPAWN: f64 = 0.3;
+
+/// Draws the playfield as it looks at one moment, as SVG.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playfield {
+	/// Width and height of the image in pixels.
+	pub size: f64,
+	/// Seconds a note is visible before it reaches its key or sensor. Must be positive and
+	/// finite, as notes are placed by dividing by it.
+	pub approach: f64,
+}
+
+impl Default for Playfield {
+	fn default() -> Self {
+		Playfield { size: 480., approach: 0.8 }
+	}
+}
+
+/// The point `f` of the way along a polyline, by length.
+fn along(points: &[Point], f: f64) -> Point {
+	let total: f64 = points.windows(2).map(|w| w[0].dist(w[1])).sum();
+	let mut left = total * f.clamp(0., 1.);
+	for w in points.windows(2) {
+		let d = w[0].dist(w[1]);
+		if left <= d && d > 0. {
+			return w[0].lerp(w[1], left / d);
+		}
+		left -= d;
+	}
+	points.last().copied().unwrap_or(Point::ORIGIN)
+}
+
+/// Path of a slide track from its start key to its end key.
+fn track_points(slide: &Slide, track: &SlideTrack) -> Vec<Point> {
+	let end = track.path().last().map_or(slide.key, |(_, k)| *k);
+	let mut points = vec![slide.key.pos()];
+	points.extend(track.points(slide.key));
+	points.push(end.pos());
+	points
+}
+
+impl Playfield {
+	/// How far out a note arriving at `arrival` is at `time`, from [`SPAWN`] to 1 on the ring.
+	fn radius(&self, arrival: f64, time: f64) -> f64 {
+		let progress = 1. - (arrival - time) / self.approach;
+		SPAWN + (1. - SPAWN) * progress.clamp(0., 1.)
+	}
+
+	fn visible(&self, note: &TimedNote, time: f64) -> bool {
+		note.time - self.approach <= time && time <= note.end_time() + SIMULTANEOUS
+	}
+
+	pub fn render(&self, timeline: &Timeline, time: f64) -> String {
+		let mut svg = Svg::new(self.size, self.size, [-1.2, -1.2, 2.4, 2.4]);
+		svg.element(
+			"rect",
+			&[
+				("x", num(-1.2)),
+				("y", num(-1.2)),
+				("width", num(2.4)),
+				("height", num(2.4)),
+				("style", "fill:#111111".to_string()),
+			],
+		);
+		for sensor in Sensor::all() {
+			svg.polygon(&sensor.polygon(), "fill:none;stroke:#3a3a3a;stroke-width:0.006");
+		}
+		svg.circle(Point::ORIGIN, 1., "fill:none;stroke:#bbbbbb;stroke-width:0.015");
+		for key in Key::ALL {
+			let pos = Point::polar(key.angle(), 1.1);
+			svg.circle(pos, 0.05, "fill:#2a2a2a;stroke:#bbbbbb;stroke-width:0.01");
+			svg.text(
+				&[
+					("x", num(pos.x)),
+					("y", num(pos.y + 0.02)),
+					("style", "fill:#bbbbbb;font-size:0.06px;text-anchor:middle".to_string()),
+				],
+				&(key as u8).to_string(),
+			);
+		}
+
+		// notes drawn later cover earlier ones, so the next notes to hit end up on top
+		let notes: Vec<&TimedNote> =
+			timeline.reachable().rev().filter(|n| self.visible(n, time)).collect();
+		let color = |note, style, normal| note_color(timeline, note, style, normal);
+
+		for note in &notes {
+			let Item::Slide(slide) = &note.item else { continue };
+			for track in &slide.tracks {
+				let c = if track.style().bits() & BREAK != 0 { BREAK_COLOR } else { STAR_COLOR };
+				svg.polyline(
+					&track_points(slide, track),
+					&format!(
+						"fill:none;stroke:{};stroke-opacity:0.6;stroke-width:0.03;stroke-dasharray:0.05 0.03",
+						c
+					),
+				);
+			}
+		}
+
+		for note in &notes {
+			match &note.item {
+				Item::Hold(hold) => {
+					let s = hold.style.bits();
+					let head = Point::polar(hold.key.angle(), self.radius(note.time, time));
+					let tail = Point::polar(hold.key.angle(), self.radius(note.end_time(), time));
+					svg.line(
+						tail,
+						head,
+						&format!("stroke:{};stroke-width:0.08;stroke-linecap:round", color(note, s, TAP_COLOR)),
+					);
+					if s & EX != 0 {
+						svg.line(tail, head, "stroke:#ffffff;stroke-width:0.02;stroke-linecap:round");
+					}
+				}
+				Item::Tap(tap) if note.time >= time - SIMULTANEOUS => {
+					let s = tap.style.bits();
+					let p = Point::polar(tap.key.angle(), self.radius(note.time, time));
+					if s & (TAP_STAR | NAKED_STAR) != 0 {
+						svg.polygon(&star(p, 0.07), &fill(color(note, s, STAR_COLOR), s & EX != 0));
+					} else {
+						svg.circle(p, 0.05, &fill(color(note, s, TAP_COLOR), s & EX != 0));
+					}
+				}
+				Item::Slide(slide) if note.time >= time - SIMULTANEOUS => {
+					let s = slide.star_style.bits();
+					if s & (REMOVE | SUDDEN) == 0 {
+						let p = Point::polar(slide.key.angle(), self.radius(note.time, time));
+						svg.polygon(&star(p, 0.07), &fill(color(note, s, STAR_COLOR), s & EX != 0));
+					}
+				}
+				_ => {}
+			}
+		}
+
+		for note in &notes {
+			let (sensor, style) = match &note.item {
+				Item::TouchTap(touch) => (touch.sensor, touch.style.bits()),
+				Item::TouchHold(touch) => (touch.sensor, touch.style.bits()),
+				_ => continue,
+			};
+			let c = if style & FIREWORK != 0 { BREAK_COLOR } else { TOUCH_COLOR };
+			if time >= note.time - SIMULTANEOUS {
+				// held: the sensor lights up until the hold ends
+				svg.polygon(&sensor.polygon(), &format!("fill:{};fill-opacity:0.5", c));
+			} else {
+				let left = ((note.time - time) / self.approach).clamp(0., 1.);
+				let style = format!("fill:none;stroke:{};stroke-width:0.02", c);
+				svg.circle(sensor.center(), 0.06 + 0.18 * left, &style);
+			}
+			svg.circle(sensor.center(), 0.03, &format!("fill:{}", c));
+		}
+
+		// stars travelling on their tracks
+		for note in &notes {
+			let Item::Slide(slide) = &note.item else { continue };
+			if time < note.time {
+				continue;
+			}
+			for track in &slide.tracks {
+				let start = note.time + track.wait_secs(note.bpm);
+				let duration: f64 = track.segment_secs(note.bpm).iter().sum();
+				if time > start + duration + SIMULTANEOUS {
+					continue;
+				}
+				let f = if duration > 0. { (time - start) / duration } else { 1. };
+				let c = if track.style().bits() & BREAK != 0 { BREAK_COLOR } else { STAR_COLOR };
+				let opacity = if time < start { 0.5 } else { 1. };
+				svg.polygon(
+					&star(along(&track_points(slide, track), f), 0.07),
+					&format!("{};fill-opacity:{}", fill(c, false), num(opacity)),
+				);
+			}
+		}
+
+		svg.text(
+			&[
+				("x", num(-1.15)),
+				("y", num(-1.1)),
+				("style", "fill:#bbbbbb;font-size:0.07px".to_string()),
+			],
+			&format!("{}s", num(time)),
+		);
+		svg.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_along() {
+		let line = [Point::new(0., 0.), Point::new(1., 0.), Point::new(1., 1.)];
+		assert_eq!(along(&line, 0.25), Point::new(0.5, 0.));
+		assert_eq!(along(&line, 0.75), Point::new(1., 0.5));
+		assert_eq!(along(&line, 2.), Point::new(1., 1.));
+	}
+}
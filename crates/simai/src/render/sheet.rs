@@ -0,0 +1,229 @@
+use crate::def::*;
+use crate::layout::Point;
+use crate::parse::Chart;
+use crate::timeline::{SIMULTANEOUS, TimedNote, Timeline};
+
+use super::svg::*;
+
+// room left of the lanes for measure numbers and BPM markers, and around the sheet
+const LABELS: f64 = 64.;
+const MARGIN: f64 = 16.;
+
+/// Most measures drawn on a sheet; notes after them are left out.
+pub const MAX_MEASURES: f64 = 10_000.;
+
+fn note_style(color: &str, ex: bool) -> String {
+	let stroke = if ex { "#000000;stroke-width:2" } else { "#333333;stroke-width:0.5" };
+	format!("fill:{};stroke:{}", color, stroke)
+}
+
+/// Draws a chart as a printable score sheet: time flows down, one lane per key and a ninth
+/// lane for touches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sheet {
+	pub lane_width: f64,
+	/// Height of a whole measure, whatever its BPM.
+	pub measure_height: f64,
+}
+
+impl Default for Sheet {
+	fn default() -> Self {
+		Sheet { lane_width: 32., measure_height: 192. }
+	}
+}
+
+impl Sheet {
+	fn lane_x(&self, lane: usize) -> f64 {
+		LABELS + (lane as f64 + 0.5) * self.lane_width
+	}
+
+	fn key_x(&self, key: Key) -> f64 {
+		self.lane_x(key as usize - 1)
+	}
+
+	fn y(&self, timeline: &Timeline, time: f64) -> f64 {
+		MARGIN + timeline.tempo.measure_at(time) * self.measure_height
+	}
+
+	// notes too far in to fit on the sheet are not drawn
+	fn drawn(timeline: &Timeline, note: &TimedNote) -> bool {
+		timeline.tempo.measure_at(note.end_time()) <= MAX_MEASURES
+	}
+
+	pub fn render(&self, chart: &Chart) -> String {
+		let timeline = Timeline::from_chart(chart);
+		let y = |time: f64| self.y(&timeline, time);
+		let notes: Vec<&TimedNote> =
+			timeline.reachable().filter(|n| Self::drawn(&timeline, n)).collect();
+		let end = notes.iter().map(|n| n.end_time()).fold(0., f64::max);
+		let measures = (timeline.tempo.measure_at(end) + 1e-9).ceil().max(1.) as usize;
+
+		let width = LABELS + 9. * self.lane_width + MARGIN;
+		let height = 2. * MARGIN + measures as f64 * self.measure_height;
+		let mut svg = Svg::new(width, height, [0., 0., width, height]);
+		svg.element(
+			"rect",
+			&[("width", num(width)), ("height", num(height)), ("style", "fill:#ffffff".to_string())],
+		);
+
+		let (left, right) = (LABELS, LABELS + 9. * self.lane_width);
+		let (top, bottom) = (MARGIN, height - MARGIN);
+		for lane in 0..=9 {
+			let x = LABELS + lane as f64 * self.lane_width;
+			let style = if lane == 8 { "stroke:#888888;stroke-width:1.5" } else { "stroke:#cccccc" };
+			svg.line(Point::new(x, top), Point::new(x, bottom), style);
+		}
+		for (lane, label) in ["1", "2", "3", "4", "5", "6", "7", "8", "T"].iter().enumerate() {
+			svg.text(
+				&[
+					("x", num(self.lane_x(lane))),
+					("y", num(MARGIN - 4.)),
+					("style", "fill:#888888;font-size:10px;text-anchor:middle".to_string()),
+				],
+				label,
+			);
+		}
+		for beat in 0..=measures * 4 {
+			let y = top + beat as f64 / 4. * self.measure_height;
+			if beat % 4 == 0 {
+				svg.line(Point::new(left, y), Point::new(right, y), "stroke:#444444");
+				if beat < measures * 4 {
+					svg.text(
+						&[
+							("x", num(LABELS - 6.)),
+							("y", num(y + 10.)),
+							("style", "fill:#444444;font-size:10px;text-anchor:end".to_string()),
+						],
+						&(beat / 4 + 1).to_string(),
+					);
+				}
+			} else {
+				svg.line(Point::new(left, y), Point::new(right, y), "stroke:#eeeeee");
+			}
+		}
+		for change in timeline.tempo.changes().iter().filter(|c| c.measure <= measures as f64) {
+			let y = top + change.measure * self.measure_height;
+			svg.line(Point::new(4., y), Point::new(right, y), "stroke:#d0021b;stroke-dasharray:2 2");
+			svg.text(
+				&[("x", num(4.)), ("y", num(y - 2.)), ("style", "fill:#d0021b;font-size:9px".to_string())],
+				&format!("BPM {}", change.bpm),
+			);
+		}
+
+		let r = self.lane_width * 0.3;
+
+		// slide connectors first so the notes sit on top of them
+		for note in &notes {
+			let Item::Slide(slide) = &note.item else { continue };
+			for track in &slide.tracks {
+				let start = note.time + track.wait_secs(note.bpm);
+				let stop = start + track.segment_secs(note.bpm).iter().sum::<f64>();
+				let to = track.path().last().map_or(slide.key, |(_, k)| *k);
+				let c = if track.style().bits() & BREAK != 0 { BREAK_COLOR } else { STAR_COLOR };
+				let (x0, x1) = (self.key_x(slide.key), self.key_x(to));
+				svg.line(Point::new(x0, y(note.time)), Point::new(x0, y(start)), &format!("stroke:{}", c));
+				svg.line(
+					Point::new(x0, y(start)),
+					Point::new(x1, y(stop)),
+					&format!("stroke:{};stroke-width:2;stroke-dasharray:4 2", c),
+				);
+				svg.circle(Point::new(x1, y(stop)), r * 0.5, &format!("fill:#ffffff;stroke:{}", c));
+				svg.text(
+					&[
+						("x", num(x1 + r)),
+						("y", num(y(stop) + 3.)),
+						("style", format!("fill:{};font-size:8px", c)),
+					],
+					&track.to_string(),
+				);
+			}
+		}
+
+		for note in notes {
+			self.note(&mut svg, &timeline, note);
+		}
+		svg.finish()
+	}
+
+	fn note(&self, svg: &mut Svg, timeline: &Timeline, note: &TimedNote) {
+		let (head, tail) = (self.y(timeline, note.time), self.y(timeline, note.end_time()));
+		let r = self.lane_width * 0.3;
+		let color = |style, normal| note_color(timeline, note, style, normal);
+		let touch_x = self.lane_x(8);
+		let bar = |svg: &mut Svg, x: f64, style: String| {
+			svg.element(
+				"rect",
+				&[
+					("x", num(x - r * 0.7)),
+					("y", num(head)),
+					("width", num(r * 1.4)),
+					("height", num(tail - head)),
+					("rx", num(r * 0.3)),
+					("style", style),
+				],
+			);
+		};
+		match &note.item {
+			Item::Tap(tap) => {
+				let s = tap.style.bits();
+				let p = Point::new(self.key_x(tap.key), head);
+				if s & (TAP_STAR | NAKED_STAR) != 0 {
+					svg.polygon(&star(p, r * 1.2), &note_style(color(s, STAR_COLOR), s & EX != 0));
+				} else {
+					svg.circle(p, r, &note_style(color(s, TAP_COLOR), s & EX != 0));
+				}
+			}
+			Item::Hold(hold) => {
+				let s = hold.style.bits();
+				let x = self.key_x(hold.key);
+				bar(svg, x, note_style(color(s, TAP_COLOR), s & EX != 0));
+				svg.circle(Point::new(x, head), r, &note_style(color(s, TAP_COLOR), s & EX != 0));
+			}
+			Item::Slide(slide) => {
+				let s = slide.star_style.bits();
+				if s & (REMOVE | SUDDEN) == 0 {
+					let p = Point::new(self.key_x(slide.key), head);
+					svg.polygon(&star(p, r * 1.2), &note_style(color(s, STAR_COLOR), s & EX != 0));
+				}
+			}
+			Item::TouchTap(TouchTap { sensor, style })
+			| Item::TouchHold(TouchHold { sensor, style, .. }) => {
+				let c = if style.bits() & FIREWORK != 0 { BREAK_COLOR } else { TOUCH_COLOR };
+				// labels of touches at the same time go below each other
+				let stacked = timeline
+					.notes
+					.iter()
+					.take_while(|n| !std::ptr::eq(*n, note))
+					.filter(|n| {
+						matches!(n.item, Item::TouchTap(_) | Item::TouchHold(_))
+							&& (n.time - note.time).abs() < SIMULTANEOUS
+					})
+					.count();
+				if matches!(note.item, Item::TouchHold(_)) {
+					bar(svg, touch_x, note_style(c, false));
+				}
+				svg.circle(Point::new(touch_x, head), r * 0.8, &note_style(c, false));
+				svg.text(
+					&[
+						("x", num(touch_x + r)),
+						("y", num(head + 3. + 9. * stacked as f64)),
+						("style", "fill:#333333;font-size:8px".to_string()),
+					],
+					&sensor.to_string(),
+				);
+			}
+			_ => {}
+		}
+	}
+
+	/// [`Sheet::render`] in a standalone HTML page.
+	pub fn render_html(&self, chart: &Chart, title: &str) -> String {
+		format!(
+			"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+			 <style>body {{ margin: 0; }} @media print {{ svg {{ break-inside: auto; }} }}</style>\n\
+			 </head>\n<body>\n{}</body>\n</html>\n",
+			escape(title),
+			self.render(chart)
+		)
+	}
+}
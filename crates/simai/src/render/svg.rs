@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use crate::analysis::pressed_key;
+use crate::def::*;
+use crate::layout::Point;
+use crate::timeline::{SIMULTANEOUS, TimedNote, Timeline};
+
+pub(crate) const TAP_COLOR: &str = "#ff6fae";
+pub(crate) const EACH_COLOR: &str = "#ffd84a";
+pub(crate) const BREAK_COLOR: &str = "#ff8a1f";
+pub(crate) const STAR_COLOR: &str = "#3fb6ff";
+pub(crate) const TOUCH_COLOR: &str = "#4dd0e1";
+
+/// Color of a button note: orange for breaks, yellow when another button note arrives at the
+/// same time, `normal` otherwise.
+pub(crate) fn note_color(
+	timeline: &Timeline,
+	note: &TimedNote,
+	style: S,
+	normal: &'static str,
+) -> &'static str {
+	let each = || {
+		pressed_key(&note.item).is_some()
+			&& timeline.notes.iter().any(|n| {
+				!std::ptr::eq(n, note)
+					&& (n.time - note.time).abs() < SIMULTANEOUS
+					&& pressed_key(&n.item).is_some()
+			})
+	};
+	if style & BREAK != 0 {
+		BREAK_COLOR
+	} else if each() {
+		EACH_COLOR
+	} else {
+		normal
+	}
+}
+
+pub(crate) fn star(center: Point, r: f64) -> Vec<Point> {
+	(0..10)
+		.map(|i| {
+			let radius = if i % 2 == 0 { r } else { r * 0.45 };
+			let p = Point::polar(36. * i as f64, radius);
+			Point::new(center.x + p.x, center.y + p.y)
+		})
+		.collect()
+}
+
+pub(crate) fn fill(color: &str, ex: bool) -> String {
+	let stroke = if ex { "#ffffff" } else { "#333333" };
+	format!("fill:{};stroke:{};stroke-width:0.012", color, stroke)
+}
+
+/// A number rounded to 3 decimals, without trailing zeros or negative zero, so output does not
+/// change with float noise.
+pub(crate) fn num(x: f64) -> String {
+	let s = format!("{:.3}", x);
+	let s = s.trim_end_matches('0').trim_end_matches('.');
+	if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+pub(crate) fn points(points: &[Point]) -> String {
+	points.iter().map(|p| format!("{},{}", num(p.x), num(p.y))).collect::<Vec<_>>().join(" ")
+}
+
+pub(crate) fn escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// An SVG document written element by element.
+pub(crate) struct Svg {
+	out: String,
+}
+
+impl Svg {
+	pub fn new(width: f64, height: f64, view_box: [f64; 4]) -> Self {
+		let mut out = String::new();
+		let [x, y, w, h] = view_box.map(num);
+		let _ = writeln!(
+			out,
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+			num(width),
+			num(height),
+			x,
+			y,
+			w,
+			h
+		);
+		Svg { out }
+	}
+
+	/// Adds an element with its attributes given as name and value pairs.
+	pub fn element(&mut self, name: &str, attrs: &[(&str, String)]) {
+		self.open(name, attrs, "/>");
+	}
+
+	pub fn text(&mut self, attrs: &[(&str, String)], text: &str) {
+		self.open("text", attrs, ">");
+		let _ = writeln!(self.out, "{}</text>", escape(text));
+	}
+
+	pub fn circle(&mut self, center: Point, r: f64, style: &str) {
+		self.element(
+			"circle",
+			&[("cx", num(center.x)), ("cy", num(center.y)), ("r", num(r)), ("style", style.to_string())],
+		);
+	}
+
+	pub fn line(&mut self, a: Point, b: Point, style: &str) {
+		self.element(
+			"line",
+			&[
+				("x1", num(a.x)),
+				("y1", num(a.y)),
+				("x2", num(b.x)),
+				("y2", num(b.y)),
+				("style", style.to_string()),
+			],
+		);
+	}
+
+	pub fn polygon(&mut self, v: &[Point], style: &str) {
+		self.element("polygon", &[("points", points(v)), ("style", style.to_string())]);
+	}
+
+	pub fn polyline(&mut self, v: &[Point], style: &str) {
+		self.element("polyline", &[("points", points(v)), ("style", style.to_string())]);
+	}
+
+	pub fn finish(mut self) -> String {
+		self.out.push_str("</svg>\n");
+		self.out
+	}
+
+	fn open(&mut self, name: &str, attrs: &[(&str, String)], close: &str) {
+		let _ = write!(self.out, "<{}", name);
+		for (attr, value) in attrs {
+			let _ = write!(self.out, r#" {}="{}""#, attr, escape(value));
+		}
+		self.out.push_str(close);
+		if close == "/>" {
+			self.out.push('\n');
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_num() {
+		assert_eq!(num(1.), "1");
+		assert_eq!(num(-0.0001), "0");
+		assert_eq!(num(0.12345), "0.123");
+		assert_eq!(num(-2.5), "-2.5");
+	}
+}
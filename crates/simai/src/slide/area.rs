@@ -0,0 +1,190 @@
+use crate::def::{Key, Sensor, SensorGroup, Shape, SlideTrack};
+
+/// One step of a slide's judgment sequence. Touching any of the sensors clears the step;
+/// every step has a single sensor except for the fan shape, which spreads over three lanes.
+pub type Area = Vec<Sensor>;
+
+// The tables below are written for a slide starting at key 1 and going clockwise. Sensors are
+// stored as an offset from the start key, so rotating is adding the start key and mirroring is
+// negating the offset. This only holds for the A, B and C groups, which are aligned with the
+// keys, and they are the only ones a slide goes through.
+type Rel = (SensorGroup, i32);
+
+const C: Rel = (SensorGroup::C, 0);
+
+fn a(offset: i32) -> Rel {
+	(SensorGroup::A, offset)
+}
+
+fn b(offset: i32) -> Rel {
+	(SensorGroup::B, offset)
+}
+
+fn line(d: i32) -> Option<Vec<Rel>> {
+	Some(match d {
+		2 => vec![a(0), a(1), a(2)],
+		3 => vec![a(0), b(1), b(2), a(3)],
+		4 => vec![a(0), b(0), C, b(4), a(4)],
+		5 | 6 => mirror(line(8 - d)?),
+		_ => return None,
+	})
+}
+
+fn circle(d: i32) -> Vec<Rel> {
+	let d = if d == 0 { 8 } else { d };
+	(0..=d).map(a).collect()
+}
+
+fn v(d: i32) -> Option<Vec<Rel>> {
+	match d {
+		0 | 4 => None,
+		_ => Some(vec![a(0), b(0), C, b(d), a(d)]),
+	}
+}
+
+// The star enters the B ring one key after the start and leaves it one key before the end.
+// Ends too close to the start cannot be reached without going around once more.
+fn q(d: i32) -> Vec<Rel> {
+	let n = match (d + 7) % 8 {
+		n if n < 2 => n + 8,
+		n => n,
+	};
+	let mut v = vec![a(0)];
+	v.extend((1..=n).map(b));
+	v.push(a(d));
+	v
+}
+
+// The star crosses the center, then loops around the far side of the B ring to the end.
+fn qq(d: i32) -> Vec<Rel> {
+	let mut v = vec![a(0), b(0), C];
+	v.extend((4..=4 + (d - 4).rem_euclid(8)).map(b));
+	v.push(a(d));
+	v
+}
+
+fn s(d: i32) -> Option<Vec<Rel>> {
+	match d {
+		4 => Some(vec![a(0), b(-1), b(-2), C, b(2), b(3), a(4)]),
+		_ => None,
+	}
+}
+
+fn mirror(v: Vec<Rel>) -> Vec<Rel> {
+	v.into_iter().map(|(g, o)| (g, -o)).collect()
+}
+
+fn resolve(from: Key, v: Vec<Rel>) -> Vec<Area> {
+	v.into_iter()
+		.map(|(group, o)| {
+			let index = (group != SensorGroup::C).then(|| from.rotate(o));
+			vec![Sensor { group, index }]
+		})
+		.collect()
+}
+
+/// Judgment areas of a single slide segment going from `from` to `to`, in order, including the
+/// A areas of both ends. Returns `None` if the shape cannot connect the two keys.
+pub fn segment_areas(shape: &Shape, from: Key, to: Key) -> Option<Vec<Area>> {
+	let cw = from.offset(to) as i32;
+	let ccw = to.offset(from) as i32;
+
+	let rel = match shape {
+		Shape::Line => line(cw)?,
+		Shape::ArcRight if from.is_top() => circle(cw),
+		Shape::ArcRight => mirror(circle(ccw)),
+		Shape::ArcLeft if from.is_top() => mirror(circle(ccw)),
+		Shape::ArcLeft => circle(cw),
+		Shape::Arc => match cw {
+			1..=3 => circle(cw),
+			5..=7 => mirror(circle(ccw)),
+			_ => return None,
+		},
+		Shape::V => v(cw)?,
+		Shape::Q => q(cw),
+		Shape::P => mirror(q(ccw)),
+		Shape::QQ => qq(cw),
+		Shape::PP => mirror(qq(ccw)),
+		Shape::S => s(cw)?,
+		Shape::Z => mirror(s(ccw)?),
+		Shape::Fan if cw == 4 => {
+			return Some(vec![
+				resolve(from, vec![a(0)]).concat(),
+				resolve(from, vec![b(0), b(1), b(-1)]).concat(),
+				resolve(from, vec![C, b(2), b(-2)]).concat(),
+				resolve(from, vec![a(3), a(4), a(5)]).concat(),
+			]);
+		}
+		Shape::Fan => return None,
+		Shape::Angle(mid) => {
+			let mut v = segment_areas(&Shape::Line, from, *mid)?;
+			if !matches!(from.offset(*mid), 2 | 6) {
+				return None;
+			}
+			v.extend(segment_areas(&Shape::Line, *mid, to)?.into_iter().skip(1));
+			return Some(v);
+		}
+	};
+
+	Some(resolve(from, rel))
+}
+
+impl SlideTrack {
+	/// Judgment areas of the whole track starting at `start`, with connected segments sharing
+	/// the area at their junction. Returns `None` if any segment is impossible.
+	pub fn areas(&self, start: Key) -> Option<Vec<Area>> {
+		let mut areas = Vec::new();
+		let mut from = start;
+		for (shape, to) in self.path() {
+			let seg = segment_areas(shape, from, to)?;
+			let skip = if areas.is_empty() { 0 } else { 1 };
+			areas.extend(seg.into_iter().skip(skip));
+			from = to;
+		}
+		Some(areas)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::def::{Len, SlideStyle, Wait};
+
+	fn fmt(areas: Option<Vec<Area>>) -> String {
+		let areas = areas.expect("impossible slide");
+		let steps: Vec<String> =
+			areas.iter().map(|a| a.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("|")).collect();
+		steps.join(" ")
+	}
+
+	#[test]
+	fn test_segment_areas() {
+		assert_eq!(fmt(segment_areas(&Shape::Line, Key::K1, Key::K5)), "A1 B1 C B5 A5");
+		assert_eq!(fmt(segment_areas(&Shape::Line, Key::K3, Key::K6)), "A3 B4 B5 A6");
+		assert_eq!(fmt(segment_areas(&Shape::Line, Key::K1, Key::K6)), "A1 B8 B7 A6");
+		assert_eq!(fmt(segment_areas(&Shape::ArcRight, Key::K1, Key::K3)), "A1 A2 A3");
+		assert_eq!(fmt(segment_areas(&Shape::ArcRight, Key::K5, Key::K3)), "A5 A4 A3");
+		assert_eq!(fmt(segment_areas(&Shape::Arc, Key::K2, Key::K8)), "A2 A1 A8");
+		assert_eq!(fmt(segment_areas(&Shape::Q, Key::K1, Key::K4)), "A1 B2 B3 A4");
+		assert_eq!(fmt(segment_areas(&Shape::P, Key::K1, Key::K6)), "A1 B8 B7 A6");
+		assert_eq!(fmt(segment_areas(&Shape::S, Key::K8, Key::K4)), "A8 B7 B6 C B2 B3 A4");
+		assert_eq!(fmt(segment_areas(&Shape::Z, Key::K8, Key::K4)), "A8 B1 B2 C B6 B5 A4");
+		assert_eq!(fmt(segment_areas(&Shape::Angle(Key::K7), Key::K1, Key::K4)), "A1 A8 A7 B6 B5 A4");
+		assert_eq!(fmt(segment_areas(&Shape::Fan, Key::K1, Key::K5)), "A1 B1|B2|B8 C|B3|B7 A4|A5|A6");
+
+		assert_eq!(segment_areas(&Shape::Line, Key::K1, Key::K2), None);
+		assert_eq!(segment_areas(&Shape::Arc, Key::K1, Key::K5), None);
+		assert_eq!(segment_areas(&Shape::Angle(Key::K2), Key::K1, Key::K5), None);
+	}
+
+	#[test]
+	fn test_track_areas() {
+		let track = SlideTrack::Amortized {
+			path: vec![(Shape::Line, Key::K5), (Shape::ArcRight, Key::K3)],
+			wait: Wait::Rel,
+			style: SlideStyle::empty(),
+			len: Len::Zero,
+		};
+		assert_eq!(fmt(track.areas(Key::K1)), "A1 B1 C B5 A5 A4 A3");
+	}
+}
@@ -0,0 +1,3 @@
+mod area;
+
+pub use area::*;
@@ -0,0 +1,7 @@
+mod quantize;
+mod resolve;
+mod tempo;
+
+pub use quantize::*;
+pub use resolve::*;
+pub use tempo::*;
@@ -0,0 +1,283 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::def::*;
+use crate::parse::{SimpleSpan, Spanned};
+use crate::timeline::{Step, TempoMap, Timeline};
+
+/// Divisors tried for each measure, smallest first.
+pub const DIVS: [u32; 16] = [1, 2, 3, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 128, 192, 384];
+
+/// Most measures a chart is quantized to.
+pub const MAX_MEASURES: f64 = 10_000.;
+
+// events closer than this, in measures, share a tick
+const EPS: f64 = 1e-9;
+
+/// Places notes given in seconds onto ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantizer {
+	/// Divisors to choose from, in order of preference.
+	pub divs: Vec<u32>,
+	/// How far in seconds a note may move. Measures where no divisor fits are written with
+	/// `{#x}` steps instead.
+	pub tolerance: f64,
+}
+
+impl Default for Quantizer {
+	fn default() -> Self {
+		Quantizer { divs: DIVS.to_vec(), tolerance: 0.001 }
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Quantized {
+	pub items: Vec<Item>,
+	/// Seconds each note was moved by, in the order the notes were given.
+	pub errors: Vec<f64>,
+}
+
+impl Quantized {
+	pub fn max_error(&self) -> f64 {
+		self.errors.iter().fold(0., |max, e| e.abs().max(max))
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantizeError {
+	/// The note at this index never comes, e.g. after a BPM of 0.
+	Unreachable(usize),
+	/// The note at this index comes after [`MAX_MEASURES`].
+	TooLate(usize),
+}
+
+impl Display for QuantizeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			QuantizeError::Unreachable(n) => write!(f, "note {} never comes", n + 1),
+			QuantizeError::TooLate(n) => write!(f, "note {} comes after measure {}", n + 1, MAX_MEASURES),
+		}
+	}
+}
+
+impl std::error::Error for QuantizeError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+	Bpm(f64),
+	Note(usize),
+}
+
+impl Quantizer {
+	/// Writes `notes`, given as seconds since the start of the chart, as items with one divisor
+	/// per measure. The BPM changes of `tempo` become [`Item::Bpm`]s; it must start at 0.
+	/// Changes that never come or come after [`MAX_MEASURES`] are left out, as no note
+	/// follows them.
+	pub fn quantize(
+		&self,
+		notes: &[(f64, Item)],
+		tempo: &TempoMap,
+	) -> Result<Quantized, QuantizeError> {
+		// events by measure, with their position in measures
+		let mut measures: BTreeMap<usize, Vec<(f64, Event)>> = BTreeMap::new();
+		let mut add = |m: f64, e: Event| {
+			let k = (m + EPS).floor().max(0.) as usize;
+			measures.entry(k).or_default().push((m.max(0.), e));
+		};
+		for c in tempo.changes() {
+			if c.measure <= MAX_MEASURES {
+				add(c.measure, Event::Bpm(c.bpm));
+			}
+		}
+		for (i, (time, _)) in notes.iter().enumerate() {
+			let m = tempo.measure_at(*time);
+			if !(time.is_finite() && m.is_finite()) {
+				return Err(QuantizeError::Unreachable(i));
+			}
+			if m > MAX_MEASURES {
+				return Err(QuantizeError::TooLate(i));
+			}
+			add(m, Event::Note(i));
+		}
+
+		let mut items = Vec::new();
+		let mut order = Vec::new();
+		let mut step = None;
+		// events pushed onto the start of the next measure by rounding
+		let mut carry: Vec<Event> = Vec::new();
+
+		let mut k = 0;
+		loop {
+			let events = measures.remove(&k).unwrap_or_default();
+			if events.is_empty() && carry.is_empty() {
+				// empty measures up to the next event are written as one step
+				let Some(&next) = measures.keys().next() else { break };
+				let (s, ticks) = match self.divs.first() {
+					Some(&d) => (Step::Div(d), (next - k) as u32 * d),
+					None => {
+						let secs = tempo.time_at(next as f64) - tempo.time_at(k as f64);
+						(Step::Abs((secs * 1e6).round() / 1e6), 1)
+					}
+				};
+				if step != Some(s) {
+					items.push(s.item());
+					step = Some(s);
+				}
+				items.push(Item::Tick(Tick(ticks)));
+				k = next;
+				continue;
+			}
+			let start = k as f64;
+			let fits = |d: u32| {
+				events.iter().all(|(m, _)| {
+					let snapped = start + ((m - start) * d as f64).round() / d as f64;
+					(tempo.time_at(snapped) - tempo.time_at(*m)).abs() <= self.tolerance
+				})
+			};
+
+			// (position in the measure, events) for every tick that starts a group
+			let mut stops: Vec<(f64, Vec<Event>)> = vec![(0., std::mem::take(&mut carry))];
+			let place = |stops: &mut Vec<(f64, Vec<Event>)>, pos: f64, e: Event| match stops
+				.iter_mut()
+				.find(|(p, _)| (p - pos).abs() < EPS)
+			{
+				Some((_, v)) => v.push(e),
+				None => stops.push((pos, vec![e])),
+			};
+
+			let div = self.divs.iter().copied().find(|d| fits(*d));
+			match div {
+				Some(d) => {
+					for (m, e) in &events {
+						let j = ((m - start) * d as f64).round() as u32;
+						if j >= d {
+							carry.push(*e);
+						} else {
+							place(&mut stops, j as f64, *e);
+						}
+					}
+				}
+				None => {
+					for (m, e) in &events {
+						place(&mut stops, m - start, *e);
+					}
+				}
+			}
+			stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+			for (i, (pos, events)) in stops.iter().enumerate() {
+				if let Some(bpm) = events.iter().rev().find_map(|e| match e {
+					Event::Bpm(bpm) => Some(*bpm),
+					_ => None,
+				}) {
+					items.push(Item::Bpm(Bpm(bpm)));
+				}
+
+				let next = stops.get(i + 1).map(|(p, _)| *p);
+				let (s, ticks) = match div {
+					Some(d) => (Step::Div(d), next.unwrap_or(d as f64) - pos),
+					None => {
+						let end = start + next.unwrap_or(1.);
+						let secs = tempo.time_at(end) - tempo.time_at(start + pos);
+						(Step::Abs((secs * 1e6).round() / 1e6), 1.)
+					}
+				};
+				if step != Some(s) {
+					items.push(s.item());
+					step = Some(s);
+				}
+
+				for e in events {
+					if let Event::Note(n) = e {
+						items.push(notes[*n].1.clone());
+						order.push(*n);
+					}
+				}
+				items.push(Item::Tick(Tick(ticks.round() as u32)));
+			}
+			k += 1;
+		}
+		items.push(Item::End);
+
+		// measure the error on the written chart, so rounded BPM changes are accounted for
+		let spanned: Vec<Spanned<Item>> = items
+			.iter()
+			.map(|item| Spanned { inner: item.clone(), span: SimpleSpan::from(0..0) })
+			.collect();
+		let resolved = Timeline::resolve(&spanned);
+		let mut errors = vec![0.; notes.len()];
+		for (note, n) in resolved.notes.iter().zip(order) {
+			errors[n] = note.time - notes[n].0;
+		}
+
+		Ok(Quantized { items, errors })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tap(key: Key) -> Item {
+		Item::Tap(Tap { key, style: TapStyle::empty() })
+	}
+
+	fn quantize(notes: &[(f64, Item)], tempo: &TempoMap) -> (String, Quantized) {
+		let q = Quantizer::default().quantize(notes, tempo).unwrap();
+		(crate::write::chart(&q.items), q)
+	}
+
+	#[test]
+	fn test_divisors() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		// a measure is 2 seconds
+		let notes = [
+			(0., tap(Key::K1)),
+			(0.5004, tap(Key::K2)),
+			(2., tap(Key::K3)),
+			(2. + 2. / 3., tap(Key::K4)),
+		];
+		let (text, q) = quantize(&notes, &tempo);
+		assert_eq!(text, "(120){4}1,2,,,\n{3}3,4,,\nE");
+		assert!((q.errors[1] + 0.0004).abs() < 1e-9);
+		assert!((q.max_error() - 0.0004).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_tolerance() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		let notes = [(0., tap(Key::K1)), (1.2345, tap(Key::K2))];
+		let q = Quantizer { tolerance: 1e-5, ..Default::default() }.quantize(&notes, &tempo).unwrap();
+		assert_eq!(crate::write::chart(&q.items), "(120){#1.2345}1,{#0.7655}2,E");
+		assert!(q.max_error() < 1e-9);
+	}
+
+	#[test]
+	fn test_bpm_change() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		tempo.push(1., 240.);
+		// the change lands half way through the first measure
+		let notes = [(1., tap(Key::K1)), (1.5, tap(Key::K2))];
+		let (text, _) = quantize(&notes, &tempo);
+		assert_eq!(text, "(120){2},(240)1,\n{1}2,\nE");
+	}
+
+	#[test]
+	fn test_bounds() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		// empty measures between notes are a single step
+		let (text, _) = quantize(&[(0., tap(Key::K1)), (8., tap(Key::K2))], &tempo);
+		assert_eq!(text, "(120){1}1,\n,,,\n2,\nE");
+
+		let q = |time: f64| {
+			Quantizer::default().quantize(&[(0., tap(Key::K1)), (time, tap(Key::K2))], &tempo)
+		};
+		assert_eq!(q(f64::NAN), Err(QuantizeError::Unreachable(1)));
+		assert_eq!(q(f64::INFINITY), Err(QuantizeError::Unreachable(1)));
+		assert_eq!(q(2e8), Err(QuantizeError::TooLate(1)));
+		assert!(q(2. * MAX_MEASURES).is_ok());
+	}
+}
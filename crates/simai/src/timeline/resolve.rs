@@ -0,0 +1,142 @@
+use crate::def::*;
+use crate::parse::{Chart, SimpleSpan, Spanned};
+use crate::timeline::TempoMap;
+
+/// Length of a pseudo tick (`` ` ``) in measures. Notes split by it are meant to look
+/// simultaneous without being an each, so it only needs to be short.
+pub const PSEUDO_TICK: f64 = 1. / 384.;
+
+/// Notes closer than this in seconds count as simultaneous.
+pub const SIMULTANEOUS: f64 = 1e-6;
+
+/// How long a tick is, as set by the last `{N}` or `{#N}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+	Div(u32),
+	Abs(f64),
+}
+
+impl Step {
+	pub fn secs(self, bpm: f64) -> f64 {
+		match self {
+			Step::Div(div) => 240. / bpm / div as f64,
+			Step::Abs(abs) => abs,
+		}
+	}
+
+	/// The `{N}` or `{#N}` that sets this step.
+	pub fn item(self) -> Item {
+		match self {
+			Step::Div(d) => Item::Div(Div(d)),
+			Step::Abs(abs) => Item::DivAbs(DivAbs(abs)),
+		}
+	}
+}
+
+/// A note with the time it is hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedNote {
+	/// Seconds since the start of the chart, not counting `&first`.
+	pub time: f64,
+	/// The BPM relative lengths of the note refer to.
+	pub bpm: f64,
+	pub item: Item,
+	pub span: SimpleSpan,
+}
+
+impl TimedNote {
+	/// When the note is over: the release of a hold, or the end of the last slide track.
+	pub fn end_time(&self) -> f64 {
+		let len = |len: &Len| len.to_abs(self.bpm).unwrap_or(0.);
+		match &self.item {
+			Item::Hold(hold) => self.time + len(&hold.len),
+			Item::TouchHold(touch) => self.time + len(&touch.len),
+			Item::Slide(slide) => slide
+				.tracks
+				.iter()
+				.map(|t| self.time + t.wait_secs(self.bpm) + t.segment_secs(self.bpm).iter().sum::<f64>())
+				.fold(self.time, f64::max),
+			_ => self.time,
+		}
+	}
+}
+
+impl SlideTrack {
+	/// Seconds from the tap of the star to the start of the slide.
+	pub fn wait_secs(&self, bpm: f64) -> f64 {
+		self.wait().to_abs(bpm).unwrap_or(0.)
+	}
+
+	/// Seconds each segment takes. A length given for the whole track is shared evenly.
+	pub fn segment_secs(&self, bpm: f64) -> Vec<f64> {
+		match self {
+			SlideTrack::Amortized { path, len, .. } => {
+				let total = len.to_abs(bpm).unwrap_or(0.);
+				vec![total / path.len() as f64; path.len()]
+			}
+			SlideTrack::Piecewise { path, .. } => {
+				path.iter().map(|(_, _, len)| len.to_abs(bpm).unwrap_or(0.)).collect()
+			}
+		}
+	}
+}
+
+/// The notes of a chart in order, with their times resolved from ticks, divisors and BPMs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+	pub notes: Vec<TimedNote>,
+	pub tempo: TempoMap,
+	/// Time of the end mark, if any.
+	pub end: Option<f64>,
+}
+
+impl Timeline {
+	pub fn resolve<'a>(items: impl IntoIterator<Item = &'a Spanned<Item>>) -> Self {
+		let mut timeline = Timeline::default();
+		let mut time = 0.;
+		let mut bpm = None;
+		let mut step = None;
+
+		for item in items {
+			match &item.inner {
+				Item::Bpm(Bpm(b)) => {
+					bpm = Some(*b);
+					timeline.tempo.push(time, *b);
+				}
+				Item::Div(Div(d)) => step = Some(Step::Div(*d)),
+				Item::DivAbs(DivAbs(abs)) => step = Some(Step::Abs(*abs)),
+				Item::Tick(Tick(n)) => match (step, bpm) {
+					(Some(Step::Abs(abs)), _) => time += *n as f64 * abs,
+					(Some(step), Some(bpm)) => time += *n as f64 * step.secs(bpm),
+					_ => {}
+				},
+				Item::PseudoTick(PseudoTick(n)) => {
+					if let Some(bpm) = bpm {
+						time += *n as f64 * PSEUDO_TICK * 240. / bpm;
+					}
+				}
+				Item::End => {
+					timeline.end.get_or_insert(time);
+				}
+				Item::Error => {}
+				note => timeline.notes.push(TimedNote {
+					time,
+					bpm: bpm.unwrap_or(0.),
+					item: note.clone(),
+					span: item.span,
+				}),
+			}
+		}
+
+		timeline
+	}
+
+	pub fn from_chart(chart: &Chart) -> Self {
+		Timeline::resolve(chart.notes.iter().flatten())
+	}
+
+	/// Notes that are hit and end at a finite time. Notes after a BPM of 0 never arrive.
+	pub fn reachable(&self) -> impl DoubleEndedIterator<Item = &TimedNote> {
+		self.notes.iter().filter(|n| n.time.is_finite() && n.end_time().is_finite())
+	}
+}
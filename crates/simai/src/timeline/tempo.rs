@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TempoChange {
+	/// Seconds since the start of the chart.
+	pub time: f64,
+	/// Measures (whole notes) since the start of the chart.
+	pub measure: f64,
+	pub bpm: f64,
+}
+
+/// BPM changes of a chart, for converting between seconds and measures. Before the first change
+/// the first BPM applies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TempoMap {
+	changes: Vec<TempoChange>,
+}
+
+impl TempoMap {
+	pub fn new() -> Self {
+		Default::default()
+	}
+
+	/// Adds a BPM change, which must not come before the last one. A change at the same time as
+	/// the last one replaces it.
+	pub fn push(&mut self, time: f64, bpm: f64) {
+		if let Some(last) = self.changes.last()
+			&& last.time == time
+		{
+			self.changes.pop();
+		}
+		let measure = self.measure_at(time);
+		self.changes.push(TempoChange { time, measure, bpm });
+	}
+
+	pub fn changes(&self) -> &[TempoChange] {
+		&self.changes
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+
+	fn at_time(&self, time: f64) -> Option<&TempoChange> {
+		let i = self.changes.partition_point(|c| c.time <= time);
+		self.changes.get(i.saturating_sub(1))
+	}
+
+	fn at_measure(&self, measure: f64) -> Option<&TempoChange> {
+		let i = self.changes.partition_point(|c| c.measure <= measure);
+		self.changes.get(i.saturating_sub(1))
+	}
+
+	pub fn bpm_at(&self, time: f64) -> Option<f64> {
+		self.at_time(time).map(|c| c.bpm)
+	}
+
+	pub fn measure_at(&self, time: f64) -> f64 {
+		match self.at_time(time) {
+			Some(c) => c.measure + (time - c.time) * c.bpm / 240.,
+			None => 0.,
+		}
+	}
+
+	pub fn time_at(&self, measure: f64) -> f64 {
+		match self.at_measure(measure) {
+			// a BPM of 0 never moves on, but it does start on time
+			Some(c) if measure == c.measure => c.time,
+			Some(c) => c.time + (measure - c.measure) * 240. / c.bpm,
+			None => 0.,
+		}
+	}
+
+	/// Smallest and largest BPM.
+	pub fn range(&self) -> Option<(f64, f64)> {
+		let bpms = self.changes.iter().map(|c| c.bpm);
+		let min = bpms.clone().reduce(f64::min)?;
+		let max = bpms.reduce(f64::max)?;
+		Some((min, max))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tempo_map() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		tempo.push(2., 240.);
+
+		assert_eq!(tempo.measure_at(1.), 0.5);
+		assert_eq!(tempo.measure_at(3.), 2.);
+		assert_eq!(tempo.time_at(2.), 3.);
+		assert_eq!(tempo.bpm_at(-1.), Some(120.));
+		assert_eq!(tempo.range(), Some((120., 240.)));
+	}
+
+	#[test]
+	fn test_zero_bpm() {
+		let mut tempo = TempoMap::new();
+		tempo.push(0., 120.);
+		tempo.push(1., 0.);
+
+		assert_eq!(tempo.measure_at(5.), 0.5);
+		assert_eq!(tempo.time_at(0.5), 1.);
+		assert_eq!(tempo.time_at(0.75), f64::INFINITY);
+	}
+}
@@ -0,0 +1,162 @@
+use crate::def::*;
+use crate::parse::{Chart, Spanned};
+
+/// A symmetry of the playfield, applied to keys, sensors and slide shapes alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+	/// Swaps left and right: key 1 with 8, 2 with 7, and so on.
+	MirrorH,
+	/// Swaps top and bottom: key 1 with 4, 2 with 3, and so on.
+	MirrorV,
+	/// Rotates clockwise by the given number of keys.
+	Rotate(i32),
+}
+
+impl Transform {
+	/// Where a point at `angle` degrees clockwise from the top ends up.
+	pub fn angle(self, angle: f64) -> f64 {
+		let angle = match self {
+			Transform::MirrorH => -angle,
+			Transform::MirrorV => 180. - angle,
+			Transform::Rotate(n) => angle + 45. * n as f64,
+		};
+		angle.rem_euclid(360.)
+	}
+
+	/// Whether the transform turns clockwise motion into counter-clockwise motion.
+	pub fn reflects(self) -> bool {
+		!matches!(self, Transform::Rotate(_))
+	}
+
+	pub fn key(self, key: Key) -> Key {
+		key_at(self.angle(key.angle()))
+	}
+
+	pub fn sensor(self, sensor: Sensor) -> Sensor {
+		let angle = self.angle(sensor.angle());
+		let index = match sensor.group {
+			SensorGroup::A | SensorGroup::B => key_at(angle),
+			SensorGroup::D | SensorGroup::E => key_at(angle + 22.5),
+			SensorGroup::C => return sensor,
+		};
+		Sensor::new(sensor.group, index)
+	}
+
+	/// Maps a slide segment starting at `from`. Arcs are written relative to the start key, so
+	/// `<` and `>` may swap even under rotation.
+	pub fn shape(self, shape: &Shape, from: Key) -> Shape {
+		let swap = |a: Shape, b: Shape| if self.reflects() { b } else { a };
+		match shape {
+			Shape::ArcLeft | Shape::ArcRight => {
+				let cw = (*shape == Shape::ArcRight) == from.is_top();
+				if (cw != self.reflects()) == self.key(from).is_top() {
+					Shape::ArcRight
+				} else {
+					Shape::ArcLeft
+				}
+			}
+			Shape::P => swap(Shape::P, Shape::Q),
+			Shape::Q => swap(Shape::Q, Shape::P),
+			Shape::PP => swap(Shape::PP, Shape::QQ),
+			Shape::QQ => swap(Shape::QQ, Shape::PP),
+			Shape::S => swap(Shape::S, Shape::Z),
+			Shape::Z => swap(Shape::Z, Shape::S),
+			Shape::Angle(k) => Shape::Angle(self.key(*k)),
+			Shape::Line | Shape::Arc | Shape::V | Shape::Fan => shape.clone(),
+		}
+	}
+
+	pub fn slide(self, slide: &Slide) -> Slide {
+		let tracks = slide
+			.tracks
+			.iter()
+			.map(|track| {
+				let mut from = slide.key;
+				let mut map = |shape: &Shape, key: Key| {
+					let shape = self.shape(shape, from);
+					from = key;
+					(shape, self.key(key))
+				};
+				match track {
+					SlideTrack::Amortized { path, wait, style, len } => SlideTrack::Amortized {
+						path: path.iter().map(|(s, k)| map(s, *k)).collect(),
+						wait: wait.clone(),
+						style: *style,
+						len: len.clone(),
+					},
+					SlideTrack::Piecewise { path, wait, style } => SlideTrack::Piecewise {
+						path: path
+							.iter()
+							.map(|(s, k, l)| {
+								let (s, k) = map(s, *k);
+								(s, k, l.clone())
+							})
+							.collect(),
+						wait: wait.clone(),
+						style: *style,
+					},
+				}
+			})
+			.collect();
+		Slide { key: self.key(slide.key), star_style: slide.star_style, tracks }
+	}
+
+	pub fn item(self, item: &Item) -> Item {
+		match item {
+			Item::Tap(tap) => Item::Tap(Tap { key: self.key(tap.key), ..tap.clone() }),
+			Item::Hold(hold) => Item::Hold(Hold { key: self.key(hold.key), ..hold.clone() }),
+			Item::TouchTap(touch) => {
+				Item::TouchTap(TouchTap { sensor: self.sensor(touch.sensor), ..touch.clone() })
+			}
+			Item::TouchHold(touch) => {
+				Item::TouchHold(TouchHold { sensor: self.sensor(touch.sensor), ..touch.clone() })
+			}
+			Item::Slide(slide) => Item::Slide(self.slide(slide)),
+			_ => item.clone(),
+		}
+	}
+
+	/// Applies the transform to every note of a chart. Spans, errors and the raw text are kept
+	/// as they are, so spans still point into the original source; write the result with
+	/// [`Chart`]'s `Display` implementation.
+	pub fn chart(self, chart: &Chart) -> Chart {
+		let notes = chart.notes.as_ref().map(|notes| {
+			notes.iter().map(|n| Spanned { inner: self.item(&n.inner), span: n.span }).collect()
+		});
+		Chart { notes, ..chart.clone() }
+	}
+}
+
+fn key_at(angle: f64) -> Key {
+	Key::K1.rotate(((angle - 22.5) / 45.).round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(t: Transform, s: &str) -> String {
+		let chart: Chart = s.parse().unwrap();
+		t.chart(&chart).to_string()
+	}
+
+	#[test]
+	fn test_mirror() {
+		let s = "(120){1}1>3[4:1]/5<3[4:1]/A2/D2/E3h[4:1],1p4[4:1]*V75[4:1]/2bx,E";
+		assert_eq!(
+			apply(Transform::MirrorH, s),
+			"(120){1}8<6[4:1]/4>6[4:1]/A7/D8/E7h[4:1],\n8q5[4:1]*V24[4:1]/7bx,\nE"
+		);
+		assert_eq!(
+			apply(Transform::MirrorV, s),
+			"(120){1}4>2[4:1]/8<2[4:1]/A3/D4/E3h[4:1],\n4q1[4:1]*V68[4:1]/3bx,\nE"
+		);
+	}
+
+	#[test]
+	fn test_rotate() {
+		let s = "(120){1}1>3[4:1]/C1/B8,2-6[4:1]-8[8:1],E";
+		assert_eq!(apply(Transform::Rotate(2), s), "(120){1}3<5[4:1]/C1/B2,\n4-8[4:1]-2[8:1],\nE");
+		assert_eq!(apply(Transform::Rotate(-1), s), "(120){1}8>2[4:1]/C1/B7,\n1-5[4:1]-7[8:1],\nE");
+	}
+}
@@ -0,0 +1,9 @@
+mod mirror;
+mod rate;
+mod shift;
+mod simplify;
+
+pub use mirror::*;
+pub use rate::*;
+pub use shift::*;
+pub use simplify::*;
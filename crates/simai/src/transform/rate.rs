@@ -0,0 +1,102 @@
+use crate::def::*;
+use crate::parse::{Chart, Simai, Spanned};
+
+/// Changes the playback rate of a chart, so it stays in sync with its audio stretched by the
+/// same factor. A rate of 1.25 plays 25% faster. Relative lengths follow the BPM and are left
+/// alone; everything given in seconds or at a fixed BPM is scaled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate(pub f64);
+
+impl Rate {
+	pub fn bpm(self, bpm: f64) -> f64 {
+		bpm * self.0
+	}
+
+	pub fn time(self, time: f64) -> f64 {
+		time / self.0
+	}
+
+	pub fn len(self, len: &Len) -> Len {
+		match len {
+			Len::Bpm { bpm, frac } => Len::Bpm { bpm: self.bpm(*bpm), frac: frac.clone() },
+			Len::Abs(abs) => Len::Abs(self.time(*abs)),
+			Len::Rel(_) | Len::Zero => len.clone(),
+		}
+	}
+
+	pub fn wait(self, wait: &Wait) -> Wait {
+		match wait {
+			Wait::Bpm(bpm) => Wait::Bpm(self.bpm(*bpm)),
+			Wait::Abs(abs) => Wait::Abs(self.time(*abs)),
+			Wait::Rel => Wait::Rel,
+		}
+	}
+
+	pub fn track(self, track: &SlideTrack) -> SlideTrack {
+		match track {
+			SlideTrack::Amortized { path, wait, style, len } => SlideTrack::Amortized {
+				path: path.clone(),
+				wait: self.wait(wait),
+				style: *style,
+				len: self.len(len),
+			},
+			SlideTrack::Piecewise { path, wait, style } => SlideTrack::Piecewise {
+				path: path.iter().map(|(s, k, l)| (s.clone(), *k, self.len(l))).collect(),
+				wait: self.wait(wait),
+				style: *style,
+			},
+		}
+	}
+
+	pub fn item(self, item: &Item) -> Item {
+		match item {
+			Item::Bpm(Bpm(bpm)) => Item::Bpm(Bpm(self.bpm(*bpm))),
+			Item::DivAbs(DivAbs(abs)) => Item::DivAbs(DivAbs(self.time(*abs))),
+			Item::Hold(hold) => Item::Hold(Hold { len: self.len(&hold.len), ..hold.clone() }),
+			Item::TouchHold(touch) => {
+				Item::TouchHold(TouchHold { len: self.len(&touch.len), ..touch.clone() })
+			}
+			Item::Slide(slide) => Item::Slide(Slide {
+				tracks: slide.tracks.iter().map(|t| self.track(t)).collect(),
+				..slide.clone()
+			}),
+			_ => item.clone(),
+		}
+	}
+
+	/// Scales every note of a chart. Spans, errors and the raw text are kept as they are.
+	pub fn chart(self, chart: &Chart) -> Chart {
+		let notes = chart.notes.as_ref().map(|notes| {
+			notes.iter().map(|n| Spanned { inner: self.item(&n.inner), span: n.span }).collect()
+		});
+		Chart { notes, ..chart.clone() }
+	}
+
+	/// Scales `&first` and every chart of the container.
+	pub fn simai(self, simai: &Simai) -> Simai {
+		Simai {
+			first: simai.first.map(|first| self.time(first)),
+			chart: simai.chart.clone().map(|c| c.map(|c| self.chart(&c))),
+			..simai.clone()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rate() {
+		let chart: Chart = "(120){4}1h[#1.5]/2-6[160#4:1],{#0.5}3-7[1##150#8:3],E".parse().unwrap();
+		assert_eq!(
+			Rate(1.5).chart(&chart).to_string(),
+			"(180){4}1h[#1]/2-6[240#4:1],{#0.3333333333333333}3-7[0.6666666666666666##225#8:3],E"
+		);
+
+		let simai: Simai = "&first=1.5\n&inote_1=(100)1,E".parse().unwrap();
+		let slow = Rate(0.75).simai(&simai);
+		assert_eq!(slow.first, Some(2.));
+		assert_eq!(slow.chart[1].as_ref().unwrap().to_string(), "(75)1,E");
+	}
+}
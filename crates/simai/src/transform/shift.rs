@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+use crate::def::*;
+use crate::parse::{Chart, Simai, SimpleSpan, Spanned};
+use crate::timeline::Step;
+
+/// Moves every note of a chart by a number of seconds, positive meaning later. Absolute waits
+/// and lengths are relative to their own note and stay as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shift(pub f64);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShiftError {
+	/// The chart does not have enough empty ticks before its first note to move it earlier.
+	NotEnoughRest { available: f64, needed: f64 },
+	/// The chart has no divisor before its first note.
+	NoDiv,
+	/// The chart has no BPM before its first note.
+	NoBpm,
+}
+
+impl Display for ShiftError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ShiftError::NotEnoughRest { available, needed } => {
+				write!(f, "needs {}s of rest before the first note, found {}s", needed, available)
+			}
+			ShiftError::NoDiv => write!(f, "no divisor before the first note"),
+			ShiftError::NoBpm => write!(f, "no BPM before the first note"),
+		}
+	}
+}
+
+impl std::error::Error for ShiftError {}
+
+impl Shift {
+	/// Shifts a chart on its own by rewriting the empty ticks before its first note: a longer
+	/// rest for positive shifts, a shorter one for negative shifts. The rest is written in ticks
+	/// of the first note's divisor when it fits, or as a single tick at a matching BPM or of a
+	/// matching absolute length.
+	pub fn chart(self, chart: &Chart) -> Result<Chart, ShiftError> {
+		let Some(notes) = &chart.notes else {
+			return Ok(chart.clone());
+		};
+
+		let (mut bpm, mut step, mut rest, mut lead) = (None, None, 0., 0);
+		for (i, item) in notes.iter().enumerate() {
+			match &item.inner {
+				Item::Bpm(Bpm(b)) => bpm = Some(*b),
+				Item::Div(Div(d)) => step = Some(Step::Div(*d)),
+				Item::DivAbs(DivAbs(abs)) => step = Some(Step::Abs(*abs)),
+				Item::Tick(Tick(n)) => {
+					rest += *n as f64
+						* match (step, bpm) {
+							(Some(Step::Abs(abs)), _) => abs,
+							(Some(step), Some(b)) => step.secs(b),
+							_ => return Err(ShiftError::NoBpm),
+						};
+					lead = i + 1;
+				}
+				Item::PseudoTick(_) | Item::Error => {}
+				_ => break,
+			}
+		}
+
+		let Some(bpm) = bpm else {
+			return Err(ShiftError::NoBpm);
+		};
+		let Some(step) = step else {
+			return Err(ShiftError::NoDiv);
+		};
+		if rest + self.0 < -1e-9 {
+			return Err(ShiftError::NotEnoughRest { available: rest, needed: -self.0 });
+		}
+		let rest = (rest + self.0).max(0.);
+
+		let mut items = match step {
+			Step::Div(div) => {
+				let tick = 240. / bpm / div as f64;
+				let ticks = (rest / tick).round();
+				if (rest / tick - ticks).abs() < 1e-6 {
+					let mut v = vec![Item::Bpm(Bpm(bpm)), Item::Div(Div(div))];
+					if ticks > 0. {
+						v.push(Item::Tick(Tick(ticks as u32)));
+					}
+					v
+				} else {
+					vec![
+						Item::Bpm(Bpm(240. / rest)),
+						Item::Div(Div(1)),
+						Item::Tick(Tick(1)),
+						Item::Bpm(Bpm(bpm)),
+						Item::Div(Div(div)),
+					]
+				}
+			}
+			// an absolute divisor ignores the BPM, so the rest is a single tick of its own length
+			Step::Abs(abs) => {
+				let mut v = vec![Item::Bpm(Bpm(bpm))];
+				let rest = (rest * 1e6).round() / 1e6;
+				if rest > 0. {
+					v.extend([Item::DivAbs(DivAbs(rest)), Item::Tick(Tick(1))]);
+				}
+				v.push(Item::DivAbs(DivAbs(abs)));
+				v
+			}
+		}
+		.into_iter()
+		.map(|inner| Spanned { inner, span: SimpleSpan::from(0..0) })
+		.collect::<Vec<_>>();
+
+		// the state of the first note's own prefix items is already part of the new rest
+		let first = notes[lead..]
+			.iter()
+			.position(|n| !matches!(n.inner, Item::Bpm(_) | Item::Div(_) | Item::DivAbs(_)))
+			.map_or(notes.len(), |i| lead + i);
+		items.extend(notes[first..].iter().cloned());
+
+		Ok(Chart { notes: Some(items), ..chart.clone() })
+	}
+
+	/// Shifts a whole container through `&first`. If that would make it negative, `&first`
+	/// becomes zero and the remainder is taken from the rest before the first note of every
+	/// chart instead.
+	pub fn simai(self, simai: &Simai) -> Result<Simai, ShiftError> {
+		let first = simai.first.unwrap_or(0.) + self.0;
+		if first >= 0. {
+			return Ok(Simai { first: Some(first), ..simai.clone() });
+		}
+
+		let mut chart = simai.chart.clone();
+		for c in chart.iter_mut().flatten() {
+			*c = Shift(first).chart(c)?;
+		}
+		Ok(Simai { first: Some(0.), chart, ..simai.clone() })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn shift(s: &str, d: f64) -> Result<String, ShiftError> {
+		let chart: Chart = s.parse().unwrap();
+		Shift(d).chart(&chart).map(|c| c.to_string())
+	}
+
+	#[test]
+	fn test_shift_chart() {
+		let s = "(120){4},,(150){8}1,E";
+		assert_eq!(shift(s, 0.4).as_deref(), Ok("(150){8},,,,,,,1,\nE"));
+		assert_eq!(shift(s, -1.).as_deref(), Ok("(150){8}1,E"));
+		assert_eq!(shift(s, 0.3).as_deref(), Ok("(184.6153846153846){1},\n(150){8}1,E"));
+		assert_eq!(shift(s, -1.5), Err(ShiftError::NotEnoughRest { available: 1., needed: 1.5 }));
+		assert_eq!(shift("{#0.5},1,E", 1.), Err(ShiftError::NoBpm));
+		assert_eq!(shift("(120)1,E", 1.), Err(ShiftError::NoDiv));
+		assert_eq!(shift("(120){#0.5},1,E", 0.25).as_deref(), Ok("(120){#0.75},{#0.5}1,E"));
+		assert_eq!(shift("(120){#0.5},1,E", -0.5).as_deref(), Ok("(120){#0.5}1,E"));
+	}
+
+	#[test]
+	fn test_shift_simai() {
+		let simai: Simai = "&first=0.2\n&inote_1=(120){4},,1,E".parse().unwrap();
+		let later = Shift(0.3).simai(&simai).unwrap();
+		assert_eq!(later.first, Some(0.5));
+
+		let earlier = Shift(-0.7).simai(&simai).unwrap();
+		assert_eq!(earlier.first, Some(0.));
+		assert_eq!(earlier.chart[1].as_ref().unwrap().to_string(), "(120){4},1,E");
+	}
+}
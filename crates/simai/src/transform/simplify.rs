@@ -0,0 +1,191 @@
+use std::fmt::Display;
+
+use crate::def::*;
+use crate::parse::{Chart, SimpleSpan, Spanned};
+use crate::slide::segment_areas;
+use crate::timeline::{DIVS, QuantizeError, Quantizer, SIMULTANEOUS, TempoMap, Timeline};
+
+/// Derives an easier chart for practice: touch notes are removed, every note moves to the
+/// nearest `1/grid` of a measure, notes at the same time become a single tap, notes too close
+/// after the previous one are dropped, breaks become normal notes and slides become one
+/// straight `-` track.
+///
+/// Density is thinned note by note rather than over a window: keeping every note at least
+/// `min_gap` seconds after the last one kept caps the chart at `1 / min_gap` notes per second,
+/// while sparse passages stay as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simplify {
+	/// Divisor of the grid notes are moved onto, e.g. 4 for quarter notes. One of [`DIVS`].
+	pub grid: u32,
+	/// Seconds a note must come after the previous one to be kept.
+	pub min_gap: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimplifyError {
+	/// The grid is not one of [`DIVS`], so the result could not be written with simple divisors.
+	Grid(u32),
+	/// The gap is negative or not finite.
+	MinGap(f64),
+	/// The simplified notes could not be written, e.g. as they come too late.
+	Quantize(QuantizeError),
+}
+
+impl Display for SimplifyError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SimplifyError::Grid(grid) => write!(f, "grid must be one of {:?}, not {}", DIVS, grid),
+			SimplifyError::MinGap(gap) => {
+				write!(f, "minimum gap must be a non-negative number of seconds, not {}", gap)
+			}
+			SimplifyError::Quantize(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl std::error::Error for SimplifyError {}
+
+impl Default for Simplify {
+	fn default() -> Self {
+		Simplify { grid: 8, min_gap: 0.25 }
+	}
+}
+
+impl Simplify {
+	// the time at the nearest grid line
+	fn snap(&self, tempo: &TempoMap, time: f64) -> f64 {
+		let grid = self.grid as f64;
+		tempo.time_at((tempo.measure_at(time) * grid).round() / grid)
+	}
+
+	// `secs` as a whole number of grid steps at `bpm`, at least one
+	fn len(&self, secs: f64, bpm: f64) -> Len {
+		let steps = (secs * bpm / 240. * self.grid as f64).round().max(1.);
+		Len::Rel(Frac::new(steps as u64, self.grid as u64))
+	}
+
+	fn track(&self, key: Key, track: &SlideTrack, bpm: f64) -> SlideTrack {
+		let end = track.path().last().map_or(key, |(_, k)| *k);
+		let end = if segment_areas(&Shape::Line, key, end).is_some() { end } else { key.rotate(4) };
+		let secs: f64 = track.segment_secs(bpm).iter().sum();
+		SlideTrack::Amortized {
+			path: vec![(Shape::Line, end)],
+			wait: track.wait().clone(),
+			style: SlideStyle::empty(),
+			len: self.len(secs, bpm),
+		}
+	}
+
+	/// The simplified form of a note hit at a BPM of `bpm`, or `None` for touch notes.
+	pub fn item(&self, item: &Item, bpm: f64) -> Option<Item> {
+		let strip = |bits: u8| bits & !BREAK;
+		Some(match item {
+			Item::Tap(tap) => Item::Tap(Tap {
+				key: tap.key,
+				style: TapStyle::from_bits_truncate(strip(tap.style.bits())),
+			}),
+			Item::Hold(hold) => Item::Hold(Hold {
+				key: hold.key,
+				style: HoldStyle::from_bits_truncate(strip(hold.style.bits())),
+				len: self.len(hold.len.to_abs(bpm).unwrap_or(0.), bpm),
+			}),
+			Item::Slide(slide) => Item::Slide(Slide {
+				key: slide.key,
+				star_style: StarStyle::from_bits_truncate(strip(slide.star_style.bits())),
+				tracks: slide.tracks.first().map(|t| self.track(slide.key, t, bpm)).into_iter().collect(),
+			}),
+			Item::TouchTap(_) | Item::TouchHold(_) => return None,
+			item => item.clone(),
+		})
+	}
+
+	/// Checks the grid and gap are usable.
+	pub fn check(&self) -> Result<(), SimplifyError> {
+		if !DIVS.contains(&self.grid) {
+			return Err(SimplifyError::Grid(self.grid));
+		}
+		if !(self.min_gap >= 0. && self.min_gap.is_finite()) {
+			return Err(SimplifyError::MinGap(self.min_gap));
+		}
+		Ok(())
+	}
+
+	pub fn chart(&self, chart: &Chart) -> Result<Chart, SimplifyError> {
+		self.check()?;
+		let timeline = Timeline::from_chart(chart);
+		let tempo = &timeline.tempo;
+
+		let mut notes: Vec<(f64, Item)> = vec![];
+		for note in timeline.reachable() {
+			if matches!(note.item, Item::TouchTap(_) | Item::TouchHold(_)) {
+				continue;
+			}
+			let time = self.snap(tempo, note.time);
+			let bpm = tempo.bpm_at(time).unwrap_or(note.bpm);
+			let Some(item) = self.item(&note.item, bpm) else { continue };
+			match notes.last_mut() {
+				// an each becomes a tap on the key of its first note
+				Some((last, first)) if (time - *last).abs() < SIMULTANEOUS => {
+					if let Item::Hold(Hold { key, style, .. }) = first {
+						*first =
+							Item::Tap(Tap { key: *key, style: TapStyle::from_bits_truncate(style.bits()) });
+					} else if let Item::Slide(Slide { key, star_style, .. }) = first {
+						let style = star_style.bits() & EX;
+						*first = Item::Tap(Tap { key: *key, style: TapStyle::from_bits_truncate(style) });
+					}
+				}
+				Some((last, _)) if time - *last < self.min_gap => {}
+				_ => notes.push((time, item)),
+			}
+		}
+
+		let divs = DIVS.into_iter().filter(|d| self.grid.is_multiple_of(*d)).collect();
+		let items = Quantizer { divs, tolerance: 1e-6 }
+			.quantize(&notes, tempo)
+			.map_err(SimplifyError::Quantize)?
+			.items;
+		let items = items.into_iter().map(|inner| Spanned { inner, span: SimpleSpan::from(0..0) });
+		Ok(Chart { notes: Some(items.collect()), ..chart.clone() })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn simplify(text: &str, grid: u32, min_gap: f64) -> String {
+		Simplify { grid, min_gap }.chart(&text.parse().unwrap()).unwrap().to_string()
+	}
+
+	#[test]
+	fn test_simplify() {
+		assert_eq!(simplify("(120){4}1b,2,3/4,5h[4:1],E", 4, 0.1), "(120){4}1,2,3,5h[4:1],\nE");
+		assert_eq!(simplify("(120){16}1,2,3,4,5,6,7,8,E", 16, 0.25), "(120){8}1,3,5,7,,,,,\nE");
+		assert_eq!(simplify("(120){4}B1,C,1,E", 4, 0.1), "(120){2},1,\nE");
+		assert_eq!(simplify("(120){4}1h[4:1]/2,3-7[4:1]/4,E", 4, 0.1), "(120){4}1,3,,,\nE");
+		// a note on a change to a BPM of 0 is kept, the ones after it never arrive
+		assert_eq!(simplify("(120){4}1,2,(0)3,4,E", 8, 0.), "(120){4}1,2,(0)3,,\nE");
+	}
+
+	#[test]
+	fn test_check() {
+		let chart: Chart = "(120){4}1,E".parse().unwrap();
+		let simplify = |grid, min_gap| Simplify { grid, min_gap }.chart(&chart).err();
+		assert_eq!(simplify(0, 0.25), Some(SimplifyError::Grid(0)));
+		assert_eq!(simplify(5, 0.25), Some(SimplifyError::Grid(5)));
+		assert_eq!(simplify(12, -1.), Some(SimplifyError::MinGap(-1.)));
+		assert!(simplify(12, f64::NAN).is_some());
+		assert_eq!(simplify(12, 0.), None);
+
+		let chart: Chart = "(120){#100000000}1,2,E".parse().unwrap();
+		let err = Simplify::default().chart(&chart).err();
+		assert_eq!(err, Some(SimplifyError::Quantize(QuantizeError::TooLate(1))));
+	}
+
+	#[test]
+	fn test_simplify_slides() {
+		assert_eq!(simplify("(120){4}1b-5[4:1]*>3[8:3],E", 8, 0.1), "(120){1}1-5[4:1],\nE");
+		assert_eq!(simplify("(120){4}1V35[4:1],E", 8, 0.1), "(120){1}1-5[4:1],\nE");
+		assert_eq!(simplify("(120){4}1-4[16:5],E", 8, 0.1), "(120){1}1-4[8:3],\nE");
+	}
+}
@@ -0,0 +1,62 @@
+use crate::def::*;
+
+/// Writes items back to simai. Notes sharing a tick are joined with `/`, and a line break
+/// follows every tick that completes a measure, as long as the measure length is known.
+pub fn chart<'a>(items: impl IntoIterator<Item = &'a Item>) -> String {
+	let mut out = String::new();
+	let mut bpm = None;
+	let mut div = None;
+	let mut pos = Frac::from(0);
+	let mut joined = false;
+
+	for item in items {
+		match item {
+			Item::Bpm(Bpm(b)) => bpm = Some(*b),
+			Item::Div(Div(d)) => div = Some(*d),
+			Item::DivAbs(_) => div = None,
+			Item::Tick(Tick(n)) => {
+				out.push_str(&item.to_string());
+				joined = false;
+				if let Some(d) = div {
+					pos += Frac::new(*n, d);
+					if pos.denom().is_some_and(|d| *d == 1u32.into()) {
+						out.push('\n');
+					}
+				}
+				continue;
+			}
+			Item::PseudoTick(_) | Item::End => joined = false,
+			Item::Error => continue,
+			_ => {
+				if joined {
+					out.push('/');
+				}
+				joined = true;
+			}
+		}
+
+		match (item, bpm) {
+			(Item::Slide(slide), Some(bpm)) => out.push_str(&spell(slide, bpm).to_string()),
+			_ => out.push_str(&item.to_string()),
+		}
+	}
+
+	out
+}
+
+// Replaces waits that cannot be written next to their length with absolute ones.
+fn spell(slide: &Slide, bpm: f64) -> Slide {
+	let mut slide = slide.clone();
+	for track in slide.tracks.iter_mut() {
+		let (wait, len) = match track {
+			SlideTrack::Amortized { wait, len, .. } => (wait, &*len),
+			SlideTrack::Piecewise { wait, path, .. } => (wait, &path[0].2),
+		};
+		if !wait.spells(len)
+			&& let Some(abs) = wait.to_abs(bpm)
+		{
+			*wait = Wait::Abs(abs);
+		}
+	}
+	slide
+}
@@ -0,0 +1,3 @@
+mod chart;
+
+pub use chart::*;
@@ -1,5 +1,6 @@
 use chumsky::Parser;
 use insta::{assert_debug_snapshot, glob};
+use simai::parse::Chart;
 use std::fs;
 
 #[test]
@@ -32,3 +33,17 @@ fn test_local() {
 		assert_debug_snapshot!((output, errors));
 	});
 }
+
+#[test]
+fn test_roundtrip() {
+	glob!("fixtures/raw/ok/*.txt", |path| {
+		let input = fs::read_to_string(path).unwrap();
+		let chart: Chart = input.parse().unwrap();
+		let written = chart.to_string();
+		let reparsed: Chart = written.parse().unwrap();
+
+		assert!(reparsed.errors.is_empty(), "{}: {:?}", written, reparsed.errors);
+		let items = |c: &Chart| c.notes.iter().flatten().map(|n| n.inner.clone()).collect::<Vec<_>>();
+		assert_eq!(items(&chart), items(&reparsed), "{}", written);
+	});
+}
@@ -0,0 +1,36 @@
+use insta::{assert_snapshot, glob};
+use simai::convert::{Ma2, Record};
+use std::fs;
+
+fn sorted(ma2: &Ma2) -> Vec<String> {
+	let mut v: Vec<String> = ma2
+		.records
+		.iter()
+		.filter(|r| !matches!(r, Record::Met { .. }))
+		.map(|r| format!("{:?}", r))
+		.collect();
+	v.sort();
+	v
+}
+
+#[test]
+fn test_ma2_import() {
+	glob!("fixtures/ma2/*.ma2", |path| {
+		let ma2: Ma2 = fs::read_to_string(path).unwrap().parse().unwrap();
+		let chart = ma2.to_chart().unwrap();
+		assert!(chart.errors.is_empty(), "{:?}", chart.errors);
+		assert_snapshot!(chart.to_string());
+	});
+}
+
+#[test]
+fn test_ma2_roundtrip() {
+	glob!("fixtures/ma2/*.ma2", |path| {
+		let ma2: Ma2 = fs::read_to_string(path).unwrap().parse().unwrap();
+		let exported = Ma2::from_chart(&ma2.to_chart().unwrap());
+		assert_eq!(sorted(&ma2), sorted(&exported));
+
+		let reparsed: Ma2 = exported.to_string().parse().unwrap();
+		assert_eq!(exported, reparsed);
+	});
+}
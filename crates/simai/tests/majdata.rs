@@ -0,0 +1,12 @@
+use insta::{assert_snapshot, glob};
+use simai::{convert::Majdata, parse::Chart};
+use std::fs;
+
+#[test]
+fn test_majdata_export() {
+	glob!("fixtures/raw/ok/*.txt", |path| {
+		let chart: Chart = fs::read_to_string(path).unwrap().parse().unwrap();
+		let majdata = Majdata::from_chart(&chart);
+		assert_snapshot!(serde_json::to_string_pretty(&majdata).unwrap());
+	});
+}
@@ -0,0 +1,13 @@
+use insta::{assert_snapshot, glob};
+use simai::convert::Osu;
+use std::fs;
+
+#[test]
+fn test_osu_import() {
+	glob!("fixtures/osu/*.osu", |path| {
+		let osu: Osu = fs::read_to_string(path).unwrap().parse().unwrap();
+		let chart = osu.to_chart(&Osu::default_mapping(osu.columns).unwrap()).unwrap();
+		assert!(chart.errors.is_empty(), "{:?}", chart.errors);
+		assert_snapshot!(format!("&first={}\n{}", osu.first(), chart));
+	});
+}
@@ -0,0 +1,35 @@
+use insta::assert_snapshot;
+use simai::{
+	parse::Chart,
+	render::{Playfield, Sheet},
+	timeline::Timeline,
+};
+
+const CHART: &str = "(120){4}1,2b/6,3h[2:1],1-5[4:1],B2/C,Ch[4:1]f,4x$,E";
+
+#[test]
+fn test_playfield() {
+	let timeline = Timeline::from_chart(&CHART.parse::<Chart>().unwrap());
+	let playfield = Playfield::default();
+	for time in [0.25, 1.2, 2.0, 2.9] {
+		assert_snapshot!(format!("playfield_{}", time), playfield.render(&timeline, time));
+	}
+}
+
+#[test]
+fn test_sheet() {
+	let chart: Chart = "(120){4}1,2b/6,3h[2:1],1-5[4:1],B2/C,Ch[4:1]f,(180)4x$,E".parse().unwrap();
+	assert_snapshot!(Sheet::default().render(&chart));
+}
+
+#[test]
+fn test_unreachable_notes() {
+	// nothing after a BPM of 0 is ever reached
+	let text = "(120){4}1,2h[4:1],(0)3,4-8[4:1],E";
+	let timeline = Timeline::from_chart(&text.parse::<Chart>().unwrap());
+	let svgs =
+		[Sheet::default().render(&text.parse().unwrap()), Playfield::default().render(&timeline, 0.5)];
+	for svg in svgs {
+		assert!(!svg.contains("NaN") && !svg.contains("inf"), "{}", svg);
+	}
+}